@@ -0,0 +1,143 @@
+use super::{option_value, parse_alg, print_json, state_json, state_of, usage, CliResult, Format};
+use rubikcube::coord::{coordinate, state_at, PERMUTATIONS, TWISTS};
+use rubikcube::movetable::MoveTables;
+use rubikcube::notation::format_word;
+use rubikcube::rotation::{moves_with_rotations, normalize, seven_corner_state, REFERENCE};
+use rubikcube::symmetry::{canonical_under, rotations, symmetries, symmetry_classes};
+use rubikcube::{decode, Puzzle, WreathElm};
+use serde_json::json;
+
+pub fn cmd_normalize(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    if !puzzle.is_cube() {
+        return Err(anyhow::anyhow!("rotations are those of the 2x2x2").into());
+    }
+    let moves = moves_with_rotations();
+    let (text, word) = parse_alg(&moves, args)?;
+    let state = decode(&state_of(&moves, &word));
+    let (normalized, rotation) = normalize(&state, REFERENCE)?;
+    let seven = seven_corner_state(&state)?;
+    match format {
+        Format::Text => {
+            println!("state:      {}", state);
+            println!("rotation:   {}", rotation.name);
+            println!("normalized: {}", normalized);
+            println!("7 corners:  {}", seven);
+        }
+        Format::Json => print_json(&json!({
+            "alg": text,
+            "state": state_json(&state),
+            "rotation": rotation.name,
+            "normalized": state_json(&normalized),
+            "seven_corners": state_json(&seven),
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_coord(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    if !puzzle.is_cube() {
+        return Err(anyhow::anyhow!("coordinates are those of the 2x2x2").into());
+    }
+    let moves = moves_with_rotations();
+    let (text, state, coord) = match option_value(args, "--at")? {
+        Some(c) => {
+            let state = state_at(c as usize)?;
+            (None, state, c as usize)
+        }
+        None => {
+            let (text, word) = parse_alg(&moves, args)?;
+            let state = decode(&state_of(&moves, &word));
+            let c = coordinate(&state)?;
+            (Some(text), state, c)
+        }
+    };
+    match format {
+        Format::Text => {
+            println!("coordinate: {}", coord);
+            println!("state:      {}", state);
+        }
+        Format::Json => print_json(&json!({
+            "alg": text,
+            "coordinate": coord,
+            "state": state_json(&state),
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_tables(format: Format, args: &[String]) -> CliResult {
+    let tables = match args {
+        [] => MoveTables::cube(),
+        [flag, path] if flag == "--save" => {
+            let tables = MoveTables::cube();
+            tables.save(path)?;
+            tables
+        }
+        [flag, path] if flag == "--load" => MoveTables::load(path)?,
+        _ => return usage("tables takes `--save <file>` or `--load <file>`"),
+    };
+    match format {
+        Format::Text => {
+            println!("moves:        {}", tables.moves().join(" "));
+            println!("permutations: {}", PERMUTATIONS);
+            println!("twists:       {}", TWISTS);
+        }
+        Format::Json => print_json(&json!({
+            "moves": tables.moves(),
+            "permutations": PERMUTATIONS,
+            "twists": TWISTS,
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_symmetry(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    if !puzzle.is_cube() {
+        return Err(anyhow::anyhow!("symmetries are those of the 2x2x2").into());
+    }
+    let (syms, args) = match args.split_first() {
+        Some((a, rest)) if a == "--rotations" => (rotations(), rest),
+        _ => (symmetries(), args),
+    };
+    if args.is_empty() {
+        let classes = symmetry_classes(&syms);
+        match format {
+            Format::Text => println!("{}", classes),
+            Format::Json => print_json(&json!({
+                "symmetries": syms.len(),
+                "classes": classes.to_string(),
+            })),
+        }
+        return Ok(());
+    }
+
+    let moves = puzzle.moves();
+    let (text, word) = parse_alg(moves, args)?;
+    let state = decode(&state_of(moves, &word));
+    let (canonical, sym) = canonical_under(&state, &syms);
+    let mut distinct: Vec<WreathElm> = vec![];
+    for w in syms.iter().map(|s| s.apply(&state)) {
+        if !distinct.contains(&w) {
+            distinct.push(w);
+        }
+    }
+    let alg = sym.apply_word(moves, &word)?;
+    match format {
+        Format::Text => {
+            println!("state:     {}", state);
+            println!("canonical: {}", canonical);
+            println!("symmetry:  {}", sym.name);
+            println!("alg:       {}", format_word(&alg));
+            println!("images:    {}", distinct.len());
+        }
+        Format::Json => print_json(&json!({
+            "alg": text,
+            "state": state_json(&state),
+            "canonical": state_json(&canonical),
+            "symmetry": sym.name,
+            "canonical_alg": format_word(&alg),
+            "images": distinct.len(),
+        })),
+    }
+    Ok(())
+}
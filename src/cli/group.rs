@@ -0,0 +1,159 @@
+use super::{option_value, print_json, state_json, usage, CliResult, Format};
+use rubikcube::conjugacy::CornerGroup;
+use rubikcube::gap::{export_group, GapRepresentation};
+use rubikcube::polya::{
+    count_twist_patterns, cube_rotations_on_corners, cube_rotations_on_facelets,
+    symmetries_on_twists, CycleIndex,
+};
+use rubikcube::symmetry::rotations;
+use rubikcube::Puzzle;
+use serde_json::json;
+
+pub fn cmd_size(puzzle: &Puzzle, format: Format) -> CliResult {
+    let group = puzzle.group();
+    match format {
+        Format::Text => {
+            println!("puzzle:       {}", puzzle.name());
+            println!("arrangements: {}", group.permutations());
+            println!("twist rank:   {}", group.twist_rank());
+            println!("order:        {}", group.order());
+        }
+        Format::Json => print_json(&json!({
+            "puzzle": puzzle.name(),
+            "arrangements": group.permutations(),
+            "twist_rank": group.twist_rank(),
+            "order": group.order().to_string(),
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_classes(format: Format, args: &[String]) -> CliResult {
+    let group = match args {
+        [] => CornerGroup::cube(),
+        [a] if a == "--fixed" => CornerGroup::cube_fixed(),
+        _ => return usage("classes takes only `--fixed`"),
+    };
+    let classes = group.classes();
+    match format {
+        Format::Text => {
+            for class in classes.iter() {
+                println!(
+                    "{:<40} {:>9}  {}",
+                    class.to_string(),
+                    class.size,
+                    class.representative
+                );
+            }
+            println!("{} classes, {} states", classes.len(), group.order());
+        }
+        Format::Json => print_json(&json!(classes
+            .iter()
+            .map(|c| json!({
+                "type": c.to_string(),
+                "size": c.size.to_string(),
+                "representative": state_json(&c.representative),
+            }))
+            .collect::<Vec<_>>())),
+    }
+    Ok(())
+}
+
+pub fn cmd_colorings(format: Format, args: &[String]) -> CliResult {
+    if args.first().is_some_and(|a| a == "twists") {
+        return cmd_twist_patterns(format, &args[1..]);
+    }
+    let colors = option_value(args, "--colors")?.unwrap_or(6);
+    let colors = match u32::try_from(colors) {
+        Ok(c) => c,
+        Err(_) => return usage("--colors is too large"),
+    };
+    let rest = match args.iter().position(|a| a == "--colors") {
+        Some(i) => [&args[..i], &args[(i + 2).min(args.len())..]].concat(),
+        None => args.to_vec(),
+    };
+    let (points, group, n) = match rest.as_slice() {
+        [] => ("facelets", cube_rotations_on_facelets(), 24),
+        [a] if a == "facelets" => ("facelets", cube_rotations_on_facelets(), 24),
+        [a] if a == "corners" => ("corners", cube_rotations_on_corners(), 8),
+        _ => return usage("colorings takes `facelets`, `corners` or `twists`, and --colors N"),
+    };
+    let index = CycleIndex::new(&group, n)?;
+    let count = index.count_colorings(colors);
+    match format {
+        Format::Text => {
+            println!("points:      {}", points);
+            println!("cycle index: {}", index);
+            println!("colors:      {}", colors);
+            println!("colorings:   {}", count);
+        }
+        Format::Json => print_json(&json!({
+            "points": points,
+            "cycle_index": index.to_string(),
+            "colors": colors,
+            "colorings": count.to_string(),
+        })),
+    }
+    Ok(())
+}
+
+// twist vectors of the 2x2x2 up to whole-cube rotation
+fn cmd_twist_patterns(format: Format, args: &[String]) -> CliResult {
+    if !args.is_empty() {
+        return usage("colorings twists takes no options");
+    }
+    let group = symmetries_on_twists(&rotations());
+    let patterns = count_twist_patterns(&group, 8, false)?;
+    let legal = count_twist_patterns(&group, 8, true)?;
+    match format {
+        Format::Text => {
+            println!("points:   twists");
+            println!("patterns: {}", patterns);
+            println!("legal:    {}", legal);
+        }
+        Format::Json => print_json(&json!({
+            "points": "twists",
+            "patterns": patterns.to_string(),
+            "legal": legal.to_string(),
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_puzzles(format: Format) -> CliResult {
+    let names = Puzzle::bundled_names();
+    match format {
+        Format::Text => {
+            for name in names {
+                let puzzle = Puzzle::bundled(name).unwrap();
+                let moves = puzzle
+                    .moves()
+                    .generators()
+                    .iter()
+                    .map(|o| o.name.clone())
+                    .collect::<Vec<_>>();
+                println!(
+                    "{:<14} {} pieces, moves {}",
+                    name,
+                    puzzle.pieces(),
+                    moves.join(" ")
+                );
+            }
+        }
+        Format::Json => print_json(&json!(names)),
+    }
+    Ok(())
+}
+
+pub fn cmd_gap(puzzle: &Puzzle, args: &[String]) -> CliResult {
+    let rep = match args {
+        [] if puzzle.pieces() == 8 => GapRepresentation::Facelets,
+        [] => GapRepresentation::Wreath,
+        [a] if a == "facelets" => GapRepresentation::Facelets,
+        [a] if a == "wreath" => GapRepresentation::Wreath,
+        _ => return usage("gap takes `facelets` or `wreath`"),
+    };
+    print!("{}", export_group(puzzle.moves(), rep)?);
+    println!("# rubikcube: Size(G) = {}", puzzle.group().order());
+    Ok(())
+}
@@ -0,0 +1,232 @@
+mod cube;
+mod group;
+mod session;
+mod solve;
+mod state;
+
+use cube::{cmd_coord, cmd_normalize, cmd_symmetry, cmd_tables};
+use group::{cmd_classes, cmd_colorings, cmd_gap, cmd_puzzles, cmd_size};
+use rubikcube::json::{encode_wreath, ReplacementForm};
+use rubikcube::wreath::identity_matrix;
+use rubikcube::{evaluate, Matrix, MoveSet, Operation, Puzzle, WreathElm};
+use serde_json::{json, Value};
+use session::{cmd_examples, cmd_repl, cmd_run};
+use solve::{cmd_pdb, cmd_scramble, cmd_search, cmd_setup, cmd_solve};
+use state::{cmd_analyze, cmd_apply, cmd_diff, cmd_inverse, cmd_matrix, cmd_order};
+
+pub const USAGE: &str = "\
+usage: rubikcube [--format text|json] [--puzzle <name|file>] <command> [args]
+
+commands:
+  apply [--trace STYLE] <alg>       state reached by <alg>; with --trace the
+                                    state after every move as a `table`,
+                                    `jsonl` (JSON lines) or `net`, only
+                                    `jsonl` with --format json
+  inverse <alg>                     inverse of <alg> and its state
+  order <alg>                       repetitions of <alg> returning to solved
+  analyze <alg>                     cycles, twists and parity of <alg>
+  solve [--optimal [--table FILE]] <alg>
+                                    solution of the state reached by <alg>;
+                                    with --optimal a shortest one in quarter
+                                    turns, up to whole-cube rotation
+  search [--metric quarter|face] [--count N] [--max-length N] [--nodes N]
+         [--seconds N] <alg>        IDA* search for solutions of the state of
+                                    <alg> on any puzzle, printed shortest
+                                    first as they are found (default 1)
+  diff <alg> <alg>                  compare the states of two quoted algorithms
+  classes [--fixed]                 conjugacy classes of the 2x2x2 corners,
+                                    with --fixed those keeping DBL in place
+  setup [--depth N] <target>        shortest setup S with S A S^{-1} = <target>
+                                    for A in alter_book or an inverse; the
+                                    target is `(2 6 7)` for the permutation
+                                    alone or `(2 6 7) [0 ...]` with twists
+  normalize <alg>                   the state of <alg>, which may use x, y and z,
+                                    turned so that DBL is solved, and on the
+                                    other seven corners
+  coord <alg> | --at N              coordinate in 0..3674160 of the state of
+                                    <alg>, or the state at coordinate N
+  tables [--save FILE | --load FILE] move tables on the coordinates, built or
+                                    read from a file; --save never
+                                    overwrites an existing FILE
+  pdb [--table FILE]                number of states at each distance and
+                                    God's number, from the distance table
+  symmetry [--rotations] [<alg>]    the least image of the state of <alg> under
+                                    the 48 symmetries, or the 24 rotations;
+                                    without <alg> the number of legal states
+                                    up to them
+  size                              number of reachable states
+  puzzles                           bundled puzzles for --puzzle
+  colorings [facelets|corners] [--colors N]
+                                    colorings of the 2x2x2 up to whole-cube
+                                    rotation, by Polya counting (default
+                                    facelets with 6 colors)
+  colorings twists                  twist patterns of the corners up to
+                                    whole-cube rotation, all and legal ones
+  gap [facelets|wreath]             GAP script defining the group
+  scramble [--length N] [--seed S]  random move sequence
+  scramble --random-state [--table FILE] [--seed S]
+                                    shortest scramble of a uniformly random
+                                    state
+  matrix <alg>                      monomial matrix of <alg>
+  repl                              interactive session, see `help` inside
+  run <file>                        run a worksheet script
+  examples [--tex FILE]             worked examples of the worksheet,
+                                    optionally typeset as a LaTeX document
+
+--puzzle takes a bundled puzzle or a puzzle file, the default is 2x2x2.
+--table reads the distance table from FILE, or builds it and writes it
+there if FILE does not exist. Without it the table is built in memory on
+every run, which takes a while; --table is the only way to keep it.
+
+exit codes: 0 success, 1 failure, 2 usage error";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+pub enum CliError {
+    Usage(String),
+    Failure(anyhow::Error),
+}
+
+impl From<anyhow::Error> for CliError {
+    fn from(e: anyhow::Error) -> Self {
+        CliError::Failure(e)
+    }
+}
+
+pub type CliResult = Result<(), CliError>;
+
+fn usage<T>(msg: impl Into<String>) -> Result<T, CliError> {
+    Err(CliError::Usage(msg.into()))
+}
+
+fn state_json(w: &WreathElm) -> Value {
+    encode_wreath(w, ReplacementForm::Cycles)
+}
+
+fn matrix_json(m: &Matrix) -> Value {
+    json!(m
+        .iter()
+        .map(|line| line.iter().map(|n| n.map(usize::from)).collect::<Vec<_>>())
+        .collect::<Vec<_>>())
+}
+
+fn print_json(v: &Value) {
+    println!("{}", serde_json::to_string_pretty(v).unwrap());
+}
+
+fn parse_alg(moves: &MoveSet, args: &[String]) -> Result<(String, Vec<Operation>), CliError> {
+    if args.is_empty() {
+        return usage("missing <alg>");
+    }
+    let text = args.join(" ");
+    match moves.parse_word(&text) {
+        Ok(word) => Ok((text, word)),
+        Err(e) => usage(format!("cannot parse `{}`: {}", text, e)),
+    }
+}
+
+fn state_of(moves: &MoveSet, word: &[Operation]) -> Matrix {
+    evaluate(word, &identity_matrix(moves.size()))
+}
+
+fn load_puzzle(name: &str) -> Result<Puzzle, CliError> {
+    match Puzzle::bundled(name) {
+        Some(puzzle) => Ok(puzzle),
+        None => Ok(Puzzle::load(name)?),
+    }
+}
+
+fn option_value(args: &[String], name: &str) -> Result<Option<u64>, CliError> {
+    match args.iter().position(|a| a == name) {
+        Some(i) => match args.get(i + 1).map(|v| v.parse::<u64>()) {
+            Some(Ok(v)) => Ok(Some(v)),
+            _ => usage(format!("{} needs a non-negative number", name)),
+        },
+        None => Ok(None),
+    }
+}
+
+// `args` without the listed options and their values
+fn without_options(args: &[String], names: &[&str]) -> Vec<String> {
+    let mut res = vec![];
+    let mut skip = false;
+    for a in args {
+        if skip {
+            skip = false;
+        } else if names.contains(&a.as_str()) {
+            skip = true;
+        } else {
+            res.push(a.clone());
+        }
+    }
+    res
+}
+
+pub fn run(args: Vec<String>) -> CliResult {
+    let mut format = Format::Text;
+    let mut puzzle = None;
+    let mut rest = vec![];
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let (option, value) = match arg.split_once('=') {
+            Some((o, v)) if o == "--format" || o == "--puzzle" => (o.to_string(), v.to_string()),
+            _ if arg == "--format" || arg == "--puzzle" => match iter.next() {
+                Some(v) => (arg, v),
+                None => return usage(format!("{} needs a value", arg)),
+            },
+            _ => {
+                rest.push(arg);
+                continue;
+            }
+        };
+
+        match (option.as_str(), value.as_str()) {
+            ("--format", "text") => format = Format::Text,
+            ("--format", "json") => format = Format::Json,
+            ("--format", v) => return usage(format!("unknown format `{}`", v)),
+            (_, v) => puzzle = Some(load_puzzle(v)?),
+        }
+    }
+
+    let (command, args) = match rest.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => return usage("missing command"),
+    };
+
+    let puzzle = puzzle.unwrap_or_default();
+    let moves = puzzle.moves();
+    match command {
+        "apply" => cmd_apply(moves, format, args),
+        "inverse" => cmd_inverse(moves, format, args),
+        "order" => cmd_order(moves, format, args),
+        "analyze" => cmd_analyze(moves, format, args),
+        "solve" => cmd_solve(&puzzle, format, args),
+        "diff" => cmd_diff(moves, format, args),
+        "classes" => cmd_classes(format, args),
+        "search" => cmd_search(&puzzle, format, args),
+        "setup" => cmd_setup(&puzzle, format, args),
+        "normalize" => cmd_normalize(&puzzle, format, args),
+        "coord" => cmd_coord(&puzzle, format, args),
+        "tables" => cmd_tables(format, args),
+        "pdb" => cmd_pdb(&puzzle, format, args),
+        "symmetry" => cmd_symmetry(&puzzle, format, args),
+        "size" => cmd_size(&puzzle, format),
+        "puzzles" => cmd_puzzles(format),
+        "colorings" => cmd_colorings(format, args),
+        "gap" => cmd_gap(&puzzle, args),
+        "scramble" => cmd_scramble(&puzzle, format, args),
+        "matrix" => cmd_matrix(moves, format, args),
+        "repl" => cmd_repl(moves),
+        "run" => cmd_run(moves, args),
+        "examples" => cmd_examples(args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => usage(format!("unknown command `{}`", command)),
+    }
+}
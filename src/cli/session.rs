@@ -0,0 +1,56 @@
+use super::{usage, CliResult};
+use crate::worksheet;
+use rubikcube::repl::Session;
+use rubikcube::script::run_script;
+use rubikcube::MoveSet;
+use std::io::{self, Write};
+
+pub fn cmd_repl(moves: &MoveSet) -> CliResult {
+    let mut session = Session::new(moves.clone());
+    let stdin = io::stdin();
+    let mut line = String::new();
+    loop {
+        print!("> ");
+        io::stdout().flush().map_err(anyhow::Error::from)?;
+
+        line.clear();
+        if stdin.read_line(&mut line).map_err(anyhow::Error::from)? == 0 {
+            println!();
+            return Ok(());
+        }
+        if matches!(line.trim(), "quit" | "exit") {
+            return Ok(());
+        }
+
+        match session.execute(&line) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => println!("{}", out),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
+
+pub fn cmd_run(moves: &MoveSet, args: &[String]) -> CliResult {
+    let path = match args {
+        [path] => path,
+        _ => return usage("run needs exactly one <file>"),
+    };
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("cannot read {}: {}", path, e))?;
+    run_script(moves.clone(), &source, &mut io::stdout())?;
+    Ok(())
+}
+
+pub fn cmd_examples(args: &[String]) -> CliResult {
+    let tex = match args {
+        [] => None,
+        [flag, path] if flag == "--tex" => Some(path),
+        _ => return usage("examples takes only `--tex <file>`"),
+    };
+    let report = worksheet::run();
+    if let Some(path) = tex {
+        std::fs::write(path, report.to_tex())
+            .map_err(|e| anyhow::anyhow!("cannot write {}: {}", path, e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,351 @@
+use super::{
+    option_value, parse_alg, print_json, state_json, state_of, usage, without_options, CliError,
+    CliResult, Format,
+};
+use rubikcube::coord::{state_at, STATES};
+use rubikcube::ida::{Limits, Metric, OrientationDatabase, Outcome, PermutationDatabase, Search};
+use rubikcube::movetable::MoveTables;
+use rubikcube::notation::format_word;
+use rubikcube::pdb::PatternDatabase;
+use rubikcube::rng::XorShift;
+use rubikcube::setup::{find_setup, with_inverses, Target};
+use rubikcube::solver::alter_book;
+use rubikcube::{decode, MoveSet, Operation, Puzzle, Replacement, WreathElm};
+use serde_json::json;
+use std::io::{self, Write};
+use std::time::Duration;
+
+pub fn cmd_solve(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    if let Some((flag, rest)) = args.split_first() {
+        if flag == "--optimal" {
+            return cmd_optimal(puzzle, format, rest);
+        }
+    }
+    let moves = puzzle.moves();
+    let (text, word) = parse_alg(moves, args)?;
+    let state = decode(&state_of(moves, &word));
+    let solution = puzzle.solve(&state)?;
+    let operations = solution.operations();
+    match format {
+        Format::Text => {
+            println!("state:    {}", state);
+            for step in solution.steps.iter() {
+                println!(
+                    "{:<12} {:<10} {}",
+                    format!("{:?}", step.phase),
+                    step.name,
+                    step.label
+                );
+            }
+            println!("solution: {}", format_word(&operations));
+            println!("length:   {}", operations.len());
+        }
+        Format::Json => print_json(&json!({
+            "alg": text,
+            "state": state_json(&state),
+            "steps": solution
+                .steps
+                .iter()
+                .map(|s| json!({
+                    "phase": format!("{:?}", s.phase),
+                    "name": s.name,
+                    "label": s.label,
+                    "word": format_word(&s.word),
+                }))
+                .collect::<Vec<_>>(),
+            "solution": format_word(&operations),
+            "length": operations.len(),
+        })),
+    }
+    Ok(())
+}
+
+// the moves of `MoveTables::cube`, in the same order
+fn quarter_turns(moves: &MoveSet) -> Vec<Operation> {
+    let mut opes = moves.generators().to_vec();
+    opes.extend(moves.generators().iter().map(|o| moves.inverse_of(o)));
+    opes
+}
+
+// the distance table of the 2x2x2 and the arguments without `--table FILE`
+fn pattern_database(
+    puzzle: &Puzzle,
+    args: &[String],
+) -> Result<(PatternDatabase, Vec<String>), CliError> {
+    if !puzzle.is_cube() {
+        return Err(anyhow::anyhow!("the distance table is that of the 2x2x2").into());
+    }
+    let tables = MoveTables::cube();
+    match args.iter().position(|a| a == "--table") {
+        Some(i) => match args.get(i + 1) {
+            Some(path) => Ok((
+                PatternDatabase::load_or_generate(path, &tables)?,
+                [&args[..i], &args[i + 2..]].concat(),
+            )),
+            None => usage("--table needs a file"),
+        },
+        None => Ok((PatternDatabase::generate(&tables)?, args.to_vec())),
+    }
+}
+
+fn cmd_optimal(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    let (db, args) = pattern_database(puzzle, args)?;
+    let moves = puzzle.moves();
+    let (text, word) = parse_alg(moves, &args)?;
+    let state = decode(&state_of(moves, &word));
+    let solution = db.solve(&quarter_turns(moves), &state)?;
+    match format {
+        Format::Text => {
+            println!("state:    {}", state);
+            println!("solution: {}", format_word(&solution));
+            println!("length:   {}", solution.len());
+        }
+        Format::Json => print_json(&json!({
+            "alg": text,
+            "state": state_json(&state),
+            "solution": format_word(&solution),
+            "length": solution.len(),
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_pdb(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    let (db, args) = pattern_database(puzzle, args)?;
+    if !args.is_empty() {
+        return usage("pdb takes only `--table <file>`");
+    }
+    let god = db.depths().len() - 1;
+    match format {
+        Format::Text => {
+            println!("moves:  {}", db.moves().join(" "));
+            for (d, count) in db.depths().iter().enumerate() {
+                println!("{:>2} {:>9}", d, count);
+            }
+            println!("God's number: {}", god);
+        }
+        Format::Json => print_json(&json!({
+            "moves": db.moves(),
+            "depths": db.depths(),
+            "gods_number": god,
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_search(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    let metric = match args.iter().position(|a| a == "--metric") {
+        None => Metric::Quarter,
+        Some(i) => match args.get(i + 1).map(|v| v.as_str()) {
+            Some("quarter") => Metric::Quarter,
+            Some("face") => Metric::Face,
+            _ => return usage("--metric is `quarter` or `face`"),
+        },
+    };
+    let count = option_value(args, "--count")?.unwrap_or(1);
+    if count == 0 {
+        return usage("--count must be at least 1");
+    }
+    let limits = Limits {
+        max_length: option_value(args, "--max-length")?.map(|l| l as usize),
+        max_nodes: option_value(args, "--nodes")?,
+        max_time: option_value(args, "--seconds")?.map(Duration::from_secs),
+    };
+    let options = [
+        "--metric",
+        "--count",
+        "--max-length",
+        "--nodes",
+        "--seconds",
+    ];
+    let moves = puzzle.moves();
+    let (text, word) = parse_alg(moves, &without_options(args, &options))?;
+    let state = decode(&state_of(moves, &word));
+    if !puzzle.group().contains(&state) {
+        return Err(anyhow::anyhow!("illegal state: {}", state).into());
+    }
+
+    let mut search = Search::new(moves, metric);
+    let opes = search.moves();
+    search = search.with_heuristic(PermutationDatabase::largest(&opes, 1 << 22)?);
+    if let Ok(db) = OrientationDatabase::new(&opes) {
+        search = search.with_heuristic(db);
+    }
+
+    if format == Format::Text {
+        println!("state: {}", state);
+    }
+    let mut found = 0;
+    let report = search.solve(&state, limits, |solution| {
+        found += 1;
+        match format {
+            Format::Text => println!("{:>3}  {}", solution.len(), format_word(solution)),
+            Format::Json => println!(
+                "{}",
+                json!({
+                    "alg": text,
+                    "solution": format_word(solution),
+                    "length": solution.len(),
+                })
+            ),
+        }
+        let _ = io::stdout().flush();
+        found < count
+    })?;
+
+    let outcome = match report.outcome {
+        Outcome::Exhausted => "exhausted",
+        Outcome::Stopped => "found",
+        Outcome::NodeLimit => "node limit",
+        Outcome::TimeLimit => "time limit",
+    };
+    match format {
+        Format::Text => {
+            println!("outcome: {}", outcome);
+            println!("nodes:   {}", report.nodes);
+            if let Some(l) = report.length {
+                println!("all solutions up to {} moves reported", l);
+            }
+        }
+        Format::Json => println!(
+            "{}",
+            json!({
+                "outcome": outcome,
+                "nodes": report.nodes,
+                "complete_length": report.length,
+            })
+        ),
+    }
+    Ok(())
+}
+
+pub fn cmd_setup(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    if !puzzle.is_cube() {
+        return Err(anyhow::anyhow!("setup searches use the 2x2x2 alter_book").into());
+    }
+    let depth = option_value(args, "--depth")?.unwrap_or(4) as usize;
+    let text = match args.iter().position(|a| a == "--depth") {
+        Some(i) => [&args[..i], &args[(i + 2).min(args.len())..]].concat(),
+        None => args.to_vec(),
+    }
+    .join(" ");
+    if text.is_empty() {
+        return usage("missing <target>");
+    }
+    let target = if text.contains('[') {
+        text.parse::<WreathElm>().map(Target::State)
+    } else {
+        Replacement::parse_within(&text, puzzle.pieces()).map(Target::Permutation)
+    };
+    let target = match target {
+        Ok(t) => t,
+        Err(e) => return usage(format!("cannot parse `{}`: {}", text, e)),
+    };
+
+    let moves = puzzle.moves();
+    let library = with_inverses(moves, &alter_book(moves));
+    let found = find_setup(moves, &library, &target, depth)
+        .ok_or_else(|| anyhow::anyhow!("no setup of at most {} moves reaches `{}`", depth, text))?;
+    let word = found.word();
+    let state = decode(&state_of(moves, &word));
+    match format {
+        Format::Text => {
+            println!("setup:     {}", format_word(&found.setup));
+            println!(
+                "algorithm: {} {}",
+                found.algorithm.name, found.algorithm.label
+            );
+            println!("label:     {}", found.label());
+            println!("word:      {}", format_word(&word));
+            println!("state:     {}", state);
+        }
+        Format::Json => print_json(&json!({
+            "target": text,
+            "setup": format_word(&found.setup),
+            "algorithm": found.algorithm.name,
+            "label": found.label(),
+            "word": format_word(&word),
+            "state": state_json(&state),
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_scramble(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    let moves = puzzle.moves();
+    if args.iter().any(|a| a == "--random-state") {
+        return cmd_random_state(puzzle, format, args);
+    }
+    let length = option_value(args, "--length")?.unwrap_or(15) as usize;
+    let mut rng = match option_value(args, "--seed")? {
+        Some(seed) => XorShift::new(seed),
+        None => XorShift::from_time(),
+    };
+
+    let gens = moves.generators();
+    let mut word: Vec<Operation> = vec![];
+    let mut last = None;
+    while word.len() < length {
+        let i = rng.below(gens.len());
+        // with a single generator there is nothing else to alternate with
+        if gens.len() > 1 && last == Some(i) {
+            continue;
+        }
+        last = Some(i);
+        // half turns only when they still fit into `length` quarter turns
+        let kinds = if word.len() + 2 <= length { 3 } else { 2 };
+        match rng.below(kinds) {
+            0 => word.push(gens[i].clone()),
+            1 => word.push(moves.inverse_of(&gens[i])),
+            _ => {
+                word.push(gens[i].clone());
+                word.push(gens[i].clone());
+            }
+        }
+    }
+
+    let state = decode(&state_of(moves, &word));
+    match format {
+        Format::Text => {
+            println!("scramble: {}", format_word(&word));
+            println!("state:    {}", state);
+        }
+        Format::Json => print_json(&json!({
+            "scramble": format_word(&word),
+            "state": state_json(&state),
+        })),
+    }
+    Ok(())
+}
+
+fn cmd_random_state(puzzle: &Puzzle, format: Format, args: &[String]) -> CliResult {
+    let (db, args) = pattern_database(puzzle, args)?;
+    let mut rng = match option_value(&args, "--seed")? {
+        Some(seed) => XorShift::new(seed),
+        None => XorShift::from_time(),
+    };
+    let moves = puzzle.moves();
+    let target = state_at(rng.below(STATES))?;
+    // the inverse of a shortest solution
+    let word = db
+        .solve(&quarter_turns(moves), &target)?
+        .iter()
+        .rev()
+        .map(|o| moves.inverse_of(o))
+        .collect::<Vec<_>>();
+
+    let state = decode(&state_of(moves, &word));
+    match format {
+        Format::Text => {
+            println!("scramble: {}", format_word(&word));
+            println!("state:    {}", state);
+            println!("length:   {}", word.len());
+        }
+        Format::Json => print_json(&json!({
+            "scramble": format_word(&word),
+            "state": state_json(&state),
+            "length": word.len(),
+        })),
+    }
+    Ok(())
+}
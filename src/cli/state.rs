@@ -0,0 +1,213 @@
+use super::{matrix_json, parse_alg, print_json, state_json, state_of, usage, CliResult, Format};
+use rubikcube::conjugacy::conjugacy_class_of;
+use rubikcube::diff::{diff, format_nets as format_diff_nets, format_positions};
+use rubikcube::notation::format_word;
+use rubikcube::trace::{format_json_lines, format_nets, format_table, trace};
+use rubikcube::{decode, print_matrix, Mod3, MoveSet, WreathElm};
+use serde_json::json;
+
+pub fn cmd_apply(moves: &MoveSet, format: Format, args: &[String]) -> CliResult {
+    if let Some(i) = args.iter().position(|a| a == "--trace") {
+        let style = match args.get(i + 1).map(|s| s.as_str()) {
+            Some(s @ ("table" | "jsonl" | "net")) => s,
+            _ => return usage("--trace needs `table`, `jsonl` or `net`"),
+        };
+        if format == Format::Json && style != "jsonl" {
+            return usage(format!(
+                "--format json needs `--trace jsonl`, not `{}`",
+                style
+            ));
+        }
+        let mut rest = args[..i].to_vec();
+        rest.extend_from_slice(&args[i + 2..]);
+        let (_, word) = parse_alg(moves, &rest)?;
+        let steps = trace(&word, &WreathElm::e(moves.size()));
+        match style {
+            "table" => println!("{}", format_table(&steps)),
+            "jsonl" => println!("{}", format_json_lines(&steps)),
+            _ => println!("{}", format_nets(&steps)?),
+        }
+        return Ok(());
+    }
+
+    let (text, word) = parse_alg(moves, args)?;
+    let state = decode(&state_of(moves, &word));
+    match format {
+        Format::Text => {
+            println!("alg:   {}", text);
+            println!("state: {}", state);
+        }
+        Format::Json => print_json(&json!({ "alg": text, "state": state_json(&state) })),
+    }
+    Ok(())
+}
+
+pub fn cmd_inverse(moves: &MoveSet, format: Format, args: &[String]) -> CliResult {
+    let (text, word) = parse_alg(moves, args)?;
+    let inverse = word
+        .iter()
+        .rev()
+        .map(|o| moves.inverse_of(o))
+        .collect::<Vec<_>>();
+    let state = decode(&state_of(moves, &inverse));
+    match format {
+        Format::Text => {
+            println!("alg:     {}", text);
+            println!("inverse: {}", format_word(&inverse));
+            println!("state:   {}", state);
+        }
+        Format::Json => print_json(&json!({
+            "alg": text,
+            "inverse": format_word(&inverse),
+            "state": state_json(&state),
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_order(moves: &MoveSet, format: Format, args: &[String]) -> CliResult {
+    let (text, word) = parse_alg(moves, args)?;
+    let state = decode(&state_of(moves, &word));
+    let order = state.order();
+    match format {
+        Format::Text => println!("{}", order),
+        Format::Json => print_json(&json!({
+            "alg": text,
+            "order": order,
+            "periods": state
+                .periods()
+                .iter()
+                .map(|(c, twist, period)| json!({
+                    "cycle": c,
+                    "twist": usize::from(*twist),
+                    "period": period,
+                }))
+                .collect::<Vec<_>>(),
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_analyze(moves: &MoveSet, format: Format, args: &[String]) -> CliResult {
+    let (text, word) = parse_alg(moves, args)?;
+    let m = state_of(moves, &word);
+    let state = decode(&m);
+    let replacement = state.get_replacement();
+    let vector = state.get_vector();
+
+    let cycles = replacement
+        .cycles()
+        .into_iter()
+        .map(|c| {
+            let twist = c.iter().fold(Mod3::Zero, |acc, &i| acc + vector[i - 1]);
+            (c, usize::from(twist))
+        })
+        .collect::<Vec<_>>();
+    let twisted = (1..=state.size())
+        .filter(|&i| replacement.replace(i) == i && usize::from(vector[i - 1]) != 0)
+        .map(|i| (i, usize::from(vector[i - 1])))
+        .collect::<Vec<_>>();
+    let total: usize = vector.iter().map(|&m| usize::from(m)).sum::<usize>() % 3;
+    let parity = if replacement.is_odd() { "odd" } else { "even" };
+    let cycle_type = replacement.cycle_type(state.size());
+    let order = state.order();
+    let class = conjugacy_class_of(&state).ok();
+
+    match format {
+        Format::Text => {
+            println!("alg:         {}", text);
+            println!("state:       {}", state);
+            println!("parity:      {}", parity);
+            println!("cycle type:  {:?}", cycle_type);
+            for (c, twist) in cycles.iter() {
+                println!(
+                    "cycle:       ({}) twist {}",
+                    c.iter()
+                        .map(|i| i.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    twist
+                );
+            }
+            for (i, twist) in twisted.iter() {
+                println!("twisted:     {} by {}", i, twist);
+            }
+            println!("total twist: {}", total);
+            println!("order:       {}", order);
+            if let Some(class) = class.as_ref() {
+                println!("class:       {} of {} states", class, class.size);
+            }
+        }
+        Format::Json => print_json(&json!({
+            "alg": text,
+            "state": state_json(&state),
+            "parity": parity,
+            "cycle_type": cycle_type,
+            "cycles": cycles
+                .iter()
+                .map(|(c, twist)| json!({ "cycle": c, "twist": twist }))
+                .collect::<Vec<_>>(),
+            "twisted": twisted
+                .iter()
+                .map(|(i, twist)| json!({ "position": i, "twist": twist }))
+                .collect::<Vec<_>>(),
+            "total_twist": total,
+            "order": order,
+            "class": class.as_ref().map(|c| json!({
+                "type": c.to_string(),
+                "size": c.size.to_string(),
+            })),
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_diff(moves: &MoveSet, format: Format, args: &[String]) -> CliResult {
+    let (a, b) = match args {
+        [a, b] => (a, b),
+        _ => return usage("diff needs two quoted algorithms, e.g. `diff \"R U\" \"U R\"`"),
+    };
+    let (_, wa) = parse_alg(moves, std::slice::from_ref(a))?;
+    let (_, wb) = parse_alg(moves, std::slice::from_ref(b))?;
+    let (sa, sb) = (decode(&state_of(moves, &wa)), decode(&state_of(moves, &wb)));
+    let d = diff(&sa, &sb)?;
+    match format {
+        Format::Text => {
+            println!("a: {}", sa);
+            println!("b: {}", sb);
+            println!("{}", d);
+            println!();
+            println!("{}", format_positions(&sa, &sb, &d));
+            if sa.size() == 8 {
+                println!();
+                println!("{}", format_diff_nets(&sa, &sb, &d)?);
+            }
+        }
+        Format::Json => print_json(&json!({
+            "a": state_json(&sa),
+            "b": state_json(&sb),
+            "relative": state_json(&d.relative),
+            "cycles": d.named_cycles(),
+            "twists": d
+                .twists()
+                .iter()
+                .map(|&(q, m)| json!({ "position": q, "twist": usize::from(m) }))
+                .collect::<Vec<_>>(),
+            "differ": d.positions,
+        })),
+    }
+    Ok(())
+}
+
+pub fn cmd_matrix(moves: &MoveSet, format: Format, args: &[String]) -> CliResult {
+    let (text, word) = parse_alg(moves, args)?;
+    let m = state_of(moves, &word);
+    match format {
+        Format::Text => {
+            print_matrix(&text, &m);
+            println!("{}", decode(&m));
+        }
+        Format::Json => print_json(&json!({ "alg": text, "matrix": matrix_json(&m) })),
+    }
+    Ok(())
+}
@@ -0,0 +1,496 @@
+use crate::group::PuzzleGroup;
+use crate::wreath::WreathElm;
+use crate::{Mod3, Replacement};
+use anyhow::{bail, Result};
+use std::fmt::{self, Display};
+
+// In Z/3 wr S_n two elements are conjugate exactly when they have the same
+// cycle lengths with the same twist accumulated along each cycle. The legal
+// states, those with total twist zero, form a normal subgroup of index 3, and
+// such a class only splits there if every cycle has a length divisible by 3
+// and twist zero. That cannot happen unless 3 divides the number of moving
+// corners, so for the 8 corners, or 7 with one held fixed, the classes of the
+// wreath product with total twist zero are exactly the classes of the group.
+
+/// The legal corner states: `n` corners with total twist zero, optionally
+/// with corner `fixed` held in place without twist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CornerGroup {
+    n: usize,
+    fixed: Option<usize>,
+}
+
+/// A conjugacy class, given by its cycle lengths with their twists, in
+/// decreasing order and fixed points included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConjugacyClass {
+    pub cycle_type: Vec<(usize, Mod3)>,
+    pub size: u128,
+    pub representative: WreathElm,
+}
+
+fn factorial(n: usize) -> u128 {
+    (1..=n as u128).product()
+}
+
+/// Cycle lengths with accumulated twists of `w`, fixed points included.
+pub fn twisted_cycle_type(w: &WreathElm) -> Vec<(usize, Mod3)> {
+    let mut res = w
+        .periods()
+        .into_iter()
+        .map(|(c, twist, _)| (c.len(), twist))
+        .collect::<Vec<_>>();
+    res.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(usize::from(b.1).cmp(&usize::from(a.1))));
+    res
+}
+
+// partitions of `n` into parts of at most `max`, in decreasing order
+fn partitions(n: usize, max: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut res = vec![];
+    for first in (1..=max.min(n)).rev() {
+        for mut rest in partitions(n - first, first) {
+            rest.insert(0, first);
+            res.push(rest);
+        }
+    }
+    res
+}
+
+impl CornerGroup {
+    pub fn new(n: usize, fixed: Option<usize>) -> Result<Self> {
+        if let Some(f) = fixed {
+            if f == 0 || f > n {
+                bail!("corner {} does not exist", f);
+            }
+        }
+        let moving = n - fixed.iter().count();
+        if moving.is_multiple_of(3) {
+            bail!(
+                "with {} moving corners some classes split, which is not supported",
+                moving
+            );
+        }
+        Ok(Self { n, fixed })
+    }
+
+    /// All legal states of the 2x2x2.
+    pub fn cube() -> Self {
+        Self { n: 8, fixed: None }
+    }
+
+    /// The 2x2x2 with DBL (corner 6) held fixed, as when only U, R and F
+    /// are turned.
+    pub fn cube_fixed() -> Self {
+        Self {
+            n: 8,
+            fixed: Some(6),
+        }
+    }
+
+    fn moving(&self) -> usize {
+        self.n - self.fixed.iter().count()
+    }
+
+    pub fn order(&self) -> u128 {
+        let m = self.moving();
+        factorial(m) * 3u128.pow(m as u32 - 1)
+    }
+
+    pub fn contains(&self, w: &WreathElm) -> bool {
+        let total = w.get_vector().iter().fold(Mod3::Zero, |acc, &m| acc + m);
+        w.size() == self.n
+            && total == Mod3::Zero
+            && self.fixed.is_none_or(|f| {
+                w.get_replacement().replace(f) == f && w.get_vector()[f - 1] == Mod3::Zero
+            })
+    }
+
+    // the cycle type without the fixed corner
+    fn moving_type(&self, cycle_type: &[(usize, Mod3)]) -> Vec<(usize, Mod3)> {
+        let mut res = cycle_type.to_vec();
+        if self.fixed.is_some() {
+            let i = res.iter().rposition(|&c| c == (1, Mod3::Zero)).unwrap();
+            res.remove(i);
+        }
+        res
+    }
+
+    fn size_of(&self, cycle_type: &[(usize, Mod3)]) -> u128 {
+        let m = self.moving();
+        let mut centralizer = 1u128;
+        let mut i = 0;
+        while i < cycle_type.len() {
+            let k = cycle_type[i..]
+                .iter()
+                .take_while(|&&c| c == cycle_type[i])
+                .count();
+            centralizer *= factorial(k) * (3 * cycle_type[i].0 as u128).pow(k as u32);
+            i += k;
+        }
+        factorial(m) * 3u128.pow(m as u32) / centralizer
+    }
+
+    fn representative(&self, cycle_type: &[(usize, Mod3)]) -> WreathElm {
+        let points = (1..=self.n)
+            .filter(|&p| Some(p) != self.fixed)
+            .collect::<Vec<_>>();
+        let mut table = vec![];
+        let mut vector = vec![Mod3::Zero; self.n];
+        let mut next = 0;
+        for &(len, twist) in cycle_type.iter() {
+            let cycle = points[next..next + len].to_vec();
+            vector[cycle[0] - 1] = twist;
+            if len > 1 {
+                table.push(cycle);
+            }
+            next += len;
+        }
+        WreathElm::new(Replacement::new(table), vector)
+    }
+
+    fn class(&self, moving_type: Vec<(usize, Mod3)>) -> ConjugacyClass {
+        let representative = self.representative(&moving_type);
+        ConjugacyClass {
+            cycle_type: twisted_cycle_type(&representative),
+            size: self.size_of(&moving_type),
+            representative,
+        }
+    }
+
+    /// All conjugacy classes, largest cycles first.
+    pub fn classes(&self) -> Vec<ConjugacyClass> {
+        let m = self.moving();
+        let mut res = vec![];
+        for lengths in partitions(m, m) {
+            // twists per part, kept non-increasing among equal lengths
+            let mut twists = vec![vec![]];
+            for (i, _) in lengths.iter().enumerate() {
+                let mut next = vec![];
+                for t in twists.iter() {
+                    for a in (0..3).rev() {
+                        let same = i > 0 && lengths[i - 1] == lengths[i];
+                        if same && a > usize::from(*t.last().unwrap()) {
+                            continue;
+                        }
+                        let mut t = t.clone();
+                        t.push(Mod3::from(a));
+                        next.push(t);
+                    }
+                }
+                twists = next;
+            }
+            for t in twists {
+                if t.iter().fold(Mod3::Zero, |acc, &a| acc + a) != Mod3::Zero {
+                    continue;
+                }
+                res.push(self.class(lengths.iter().copied().zip(t).collect()));
+            }
+        }
+        res
+    }
+
+    /// The class of `w`, which must belong to the group.
+    pub fn class_of(&self, w: &WreathElm) -> Result<ConjugacyClass> {
+        if !self.contains(w) {
+            bail!("{} is not in the group", w);
+        }
+        Ok(self.class(self.moving_type(&twisted_cycle_type(w))))
+    }
+}
+
+/// The class of a legal state in the group of all legal states with the
+/// same number of corners.
+pub fn conjugacy_class_of(w: &WreathElm) -> Result<ConjugacyClass> {
+    CornerGroup::new(w.size(), None)?.class_of(w)
+}
+
+impl Display for ConjugacyClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}]",
+            self.cycle_type
+                .iter()
+                .map(|&(len, twist)| format!("{}:{}", len, usize::from(twist)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+// cycles of `r` on `1..=n`, fixed points included
+fn all_cycles(r: &Replacement, n: usize) -> Vec<Vec<usize>> {
+    let mut res = r.cycles();
+    res.extend((1..=n).filter(|&p| r.replace(p) == p).map(|p| vec![p]));
+    res
+}
+
+// Maps the cycles of x from the `i`-th on onto unused cycles of y of the
+// same length, every rotation in turn, until `f` returns true.
+fn search(
+    xs: &[Vec<usize>],
+    ys: &[Vec<usize>],
+    i: usize,
+    used: &mut [bool],
+    images: &mut [usize],
+    f: &mut impl FnMut(&[usize]) -> bool,
+) -> bool {
+    if i == xs.len() {
+        return f(images);
+    }
+    let c = &xs[i];
+    for j in 0..ys.len() {
+        if used[j] || ys[j].len() != c.len() {
+            continue;
+        }
+        used[j] = true;
+        for shift in 0..c.len() {
+            for (k, &p) in c.iter().enumerate() {
+                images[p - 1] = ys[j][(k + shift) % c.len()];
+            }
+            if search(xs, ys, i + 1, used, images, f) {
+                used[j] = false;
+                return true;
+            }
+        }
+        used[j] = false;
+    }
+    false
+}
+
+// Calls `f` with the images of every g on `1..=n` such that g x g^{-1} = y,
+// i.e. g carries each cycle of x onto a cycle of y, until it returns true.
+fn each_conjugator(
+    x: &Replacement,
+    y: &Replacement,
+    n: usize,
+    mut f: impl FnMut(&[usize]) -> bool,
+) {
+    if x.cycle_type(n) != y.cycle_type(n) {
+        return;
+    }
+    let xs = all_cycles(x, n);
+    let ys = all_cycles(y, n);
+    search(
+        &xs,
+        &ys,
+        0,
+        &mut vec![false; ys.len()],
+        &mut vec![0; n],
+        &mut f,
+    );
+}
+
+fn from_images(images: &[usize]) -> Replacement {
+    Replacement::from_images(images).unwrap()
+}
+
+/// Some g with g x g^{-1} = y, if x and y have the same cycle type.
+pub fn find_conjugator(x: &Replacement, y: &Replacement) -> Option<Replacement> {
+    let mut res = None;
+    each_conjugator(x, y, x.get_k().max(y.get_k()), |images| {
+        res = Some(from_images(images));
+        true
+    });
+    res
+}
+
+/// Every g on `1..=n` with g x g^{-1} = y. There are as many as elements in
+/// the centralizer of x, or none.
+pub fn conjugators(x: &Replacement, y: &Replacement, n: usize) -> Vec<Replacement> {
+    let mut res = vec![];
+    each_conjugator(x, y, n, |images| {
+        res.push(from_images(images));
+        false
+    });
+    res
+}
+
+/// Some g with g x g^{-1} = y whose arrangement is reachable in `group`.
+pub fn find_conjugator_in(
+    group: &PuzzleGroup,
+    x: &Replacement,
+    y: &Replacement,
+) -> Option<Replacement> {
+    let mut res = None;
+    each_conjugator(x, y, group.size(), |images| {
+        let g = from_images(images);
+        if group.contains_permutation(&g) {
+            res = Some(g);
+        }
+        res.is_some()
+    });
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::MoveSet;
+    use crate::puzzle::Puzzle;
+    use crate::rng::XorShift;
+    use crate::wreath::{decode, rotate};
+
+    // every element of `group`, by brute force
+    fn elements(group: &CornerGroup) -> Vec<WreathElm> {
+        let n = group.n;
+        let mut res = vec![];
+        let mut perms: Vec<Vec<usize>> = vec![vec![]];
+        for _ in 0..n {
+            let mut next = vec![];
+            for p in perms.iter() {
+                for q in (1..=n).filter(|q| !p.contains(q)) {
+                    let mut p = p.clone();
+                    p.push(q);
+                    next.push(p);
+                }
+            }
+            perms = next;
+        }
+        for p in perms.iter() {
+            for code in 0..3usize.pow(n as u32) {
+                let vector = (0..n)
+                    .map(|i| Mod3::from(code / 3usize.pow(i as u32) % 3))
+                    .collect::<Vec<_>>();
+                let w = WreathElm::new(Replacement::from_images(p).unwrap(), vector);
+                if group.contains(&w) {
+                    res.push(w);
+                }
+            }
+        }
+        res
+    }
+
+    fn key(w: &WreathElm) -> (Vec<usize>, Vec<usize>) {
+        (
+            w.get_replacement().images(w.size()),
+            w.get_vector().iter().map(|&m| m.into()).collect(),
+        )
+    }
+
+    #[test]
+    fn class_sizes_sum_to_the_order() {
+        for group in [
+            CornerGroup::cube(),
+            CornerGroup::cube_fixed(),
+            CornerGroup::new(4, None).unwrap(),
+            CornerGroup::new(5, Some(2)).unwrap(),
+        ] {
+            let classes = group.classes();
+            assert_eq!(
+                classes.iter().map(|c| c.size).sum::<u128>(),
+                group.order(),
+                "{:?}",
+                group
+            );
+            for c in classes.iter() {
+                assert!(group.contains(&c.representative));
+                assert_eq!(group.class_of(&c.representative).unwrap(), *c);
+            }
+        }
+        assert_eq!(CornerGroup::cube().order(), 88179840);
+        assert!(CornerGroup::new(3, None).is_err());
+    }
+
+    #[test]
+    fn class_of_agrees_with_conjugation() {
+        // a sample of the elements, each conjugated by the whole group
+        for (group, step) in [
+            (CornerGroup::new(4, None).unwrap(), 7),
+            (CornerGroup::new(5, Some(1)).unwrap(), 401),
+        ] {
+            let all = elements(&group);
+            assert_eq!(all.len() as u128, group.order());
+            for w in all.iter().step_by(step) {
+                let class = group.class_of(w).unwrap();
+                let mut conjugates = std::collections::HashSet::new();
+                for g in all.iter() {
+                    let c = decode(&rotate(&rotate(&g.rev(), &w.get_matrix()), &g.get_matrix()));
+                    assert_eq!(group.class_of(&c).unwrap(), class, "{} by {}", w, g);
+                    conjugates.insert(key(&c));
+                }
+                assert_eq!(conjugates.len() as u128, class.size, "{}", w);
+            }
+        }
+    }
+
+    // whether g carries each cycle of x onto one of y, i.e. g x g^{-1} = y
+    fn conjugates(g: &Replacement, x: &Replacement, y: &Replacement, n: usize) -> bool {
+        (1..=n).all(|p| g.replace(x.replace(p)) == y.replace(g.replace(p)))
+    }
+
+    fn random_permutation(rng: &mut XorShift, n: usize) -> Replacement {
+        let mut images = (1..=n).collect::<Vec<_>>();
+        for i in (1..n).rev() {
+            images.swap(i, rng.below(i + 1));
+        }
+        Replacement::from_images(&images).unwrap()
+    }
+
+    #[test]
+    fn conjugators_of_random_pairs() {
+        let mut rng = XorShift::new(5);
+        let n = 8;
+        for _ in 0..50 {
+            let x = random_permutation(&mut rng, n);
+            let h = random_permutation(&mut rng, n);
+            // y = h x h^{-1}, so that x and y have the same cycle type
+            let mut images = vec![0; n];
+            for p in 1..=n {
+                images[h.replace(p) - 1] = h.replace(x.replace(p));
+            }
+            let y = Replacement::from_images(&images).unwrap();
+            let g = find_conjugator(&x, &y).unwrap();
+            assert!(conjugates(&g, &x, &y, n), "{} {} {}", g, x, y);
+            for g in conjugators(&x, &y, n) {
+                assert!(conjugates(&g, &x, &y, n));
+            }
+        }
+    }
+
+    #[test]
+    fn different_cycle_types_are_not_conjugate() {
+        let x = "(1 2 3)".parse::<Replacement>().unwrap();
+        let y = "(1 2)(3 4)".parse::<Replacement>().unwrap();
+        assert!(find_conjugator(&x, &y).is_none());
+        assert!(conjugators(&x, &y, 4).is_empty());
+        let group = PuzzleGroup::new(&MoveSet::cube());
+        assert!(find_conjugator_in(&group, &x, &y).is_none());
+    }
+
+    #[test]
+    fn conjugators_in_a_group() {
+        let puzzle = Puzzle::bundled("skewb-corners").unwrap();
+        let group = puzzle.group();
+        let n = group.size();
+        let mut arrangements = vec![];
+        let mut rng = XorShift::new(9);
+        // every reachable arrangement, by sampling until all were seen
+        while arrangements.len() < group.permutations() {
+            let r = random_permutation(&mut rng, n);
+            if group.contains_permutation(&r) && !arrangements.contains(&r) {
+                arrangements.push(r);
+            }
+        }
+        for x in arrangements.iter() {
+            for y in arrangements.iter() {
+                let found = find_conjugator_in(&group, x, y);
+                let exists = arrangements.iter().any(|g| conjugates(g, x, y, n));
+                assert_eq!(found.is_some(), exists, "{} {}", x, y);
+                if let Some(g) = found {
+                    assert!(group.contains_permutation(&g));
+                    assert!(conjugates(&g, x, y, n));
+                }
+            }
+        }
+        // the two 3-cycles on the tetrahedron of U, R and L are conjugate
+        // in S_8 but not in the group
+        let x = "(2 4 6)".parse::<Replacement>().unwrap();
+        let y = "(2 6 4)".parse::<Replacement>().unwrap();
+        assert!(find_conjugator(&x, &y).is_some());
+        assert!(find_conjugator_in(&group, &x, &y).is_none());
+    }
+}
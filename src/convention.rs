@@ -0,0 +1,407 @@
+use crate::cube::MoveSet;
+use crate::wreath::{Operation, WreathElm};
+use crate::{Mod3, Replacement};
+use anyhow::{bail, Result};
+
+type Vec3 = [i32; 3];
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: Vec3, b: Vec3) -> i32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn unit(axis: Axis, sign: i32) -> Vec3 {
+    let mut res = [0; 3];
+    res[axis as usize] = sign;
+    res
+}
+
+/// Axes of the cube: `X` points to R, `Y` to U and `Z` to F.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub fn all() -> [Axis; 3] {
+        [Axis::X, Axis::Y, Axis::Z]
+    }
+
+    fn from_index(i: usize) -> Self {
+        Self::all()[i]
+    }
+}
+
+/// Direction in which twists are counted, seen from outside the corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TwistDirection {
+    Clockwise,
+    CounterClockwise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    U,
+    D,
+    L,
+    R,
+    F,
+    B,
+}
+
+impl Face {
+    pub fn all() -> [Face; 6] {
+        [Face::U, Face::D, Face::L, Face::R, Face::F, Face::B]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Face::U => "U",
+            Face::D => "D",
+            Face::L => "L",
+            Face::R => "R",
+            Face::F => "F",
+            Face::B => "B",
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        match self {
+            Face::U => [0, 1, 0],
+            Face::D => [0, -1, 0],
+            Face::L => [-1, 0, 0],
+            Face::R => [1, 0, 0],
+            Face::F => [0, 0, 1],
+            Face::B => [0, 0, -1],
+        }
+    }
+
+    /// Quarter turn clockwise as seen from outside the face.
+    pub fn turn(&self, v: Vec3) -> Vec3 {
+        let n = self.normal();
+        let c = cross(n, v);
+        let d = dot(n, v);
+        [n[0] * d - c[0], n[1] * d - c[1], n[2] * d - c[2]]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Corner {
+    UFR,
+    UFL,
+    UBL,
+    UBR,
+    DBR,
+    DBL,
+    DFL,
+    DFR,
+}
+
+impl Corner {
+    pub fn all() -> [Corner; 8] {
+        [
+            Corner::UFR,
+            Corner::UFL,
+            Corner::UBL,
+            Corner::UBR,
+            Corner::DBR,
+            Corner::DBL,
+            Corner::DFL,
+            Corner::DFR,
+        ]
+    }
+
+    pub fn position(&self) -> Vec3 {
+        match self {
+            Corner::UFR => [1, 1, 1],
+            Corner::UFL => [-1, 1, 1],
+            Corner::UBL => [-1, 1, -1],
+            Corner::UBR => [1, 1, -1],
+            Corner::DBR => [1, -1, -1],
+            Corner::DBL => [-1, -1, -1],
+            Corner::DFL => [-1, -1, 1],
+            Corner::DFR => [1, -1, 1],
+        }
+    }
+
+    pub fn from_position(p: Vec3) -> Self {
+        *Self::all()
+            .iter()
+            .find(|c| c.position() == p)
+            .expect("not a corner")
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Corner::UFR => "UFR",
+            Corner::UFL => "UFL",
+            Corner::UBL => "UBL",
+            Corner::UBR => "UBR",
+            Corner::DBR => "DBR",
+            Corner::DBL => "DBL",
+            Corner::DFL => "DFL",
+            Corner::DFR => "DFR",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        let mut key = s.to_uppercase().chars().collect::<Vec<_>>();
+        key.sort_unstable();
+        Self::all().into_iter().find(|c| {
+            let mut name = c.name().chars().collect::<Vec<_>>();
+            name.sort_unstable();
+            name == key
+        })
+    }
+
+    /// The axes of the three stickers, in clockwise order seen from outside,
+    /// starting from `X`.
+    pub fn clockwise(&self) -> [Axis; 3] {
+        let p = self.position();
+        let x = unit(Axis::X, p[0]);
+        let y = unit(Axis::Y, p[1]);
+        if dot(cross(x, y), p) < 0 {
+            [Axis::X, Axis::Y, Axis::Z]
+        } else {
+            [Axis::X, Axis::Z, Axis::Y]
+        }
+    }
+
+    fn index_of(&self, axis: Axis) -> usize {
+        self.clockwise().iter().position(|&a| a == axis).unwrap()
+    }
+}
+
+// Physical state of the corners: for each position (indexed as
+// `Corner::all()`), the piece sitting there and the shift `s` such that the
+// sticker at clockwise index `j` of the piece lies at index `j + s` of the
+// position.
+type Physical = Vec<(Corner, usize)>;
+
+/// Which sticker counts as orientation 0, in which direction twists are
+/// counted and how the corners are numbered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CubeConvention {
+    reference: Axis,
+    direction: TwistDirection,
+    numbering: Vec<Corner>,
+}
+
+impl Default for CubeConvention {
+    fn default() -> Self {
+        Self::worksheet()
+    }
+}
+
+impl CubeConvention {
+    /// `numbering[i]` is the corner with number `i + 1`.
+    pub fn new(reference: Axis, direction: TwistDirection, numbering: Vec<Corner>) -> Result<Self> {
+        if numbering.len() != 8 {
+            bail!("numbering must list 8 corners, got {}", numbering.len());
+        }
+        for c in Corner::all() {
+            if !numbering.contains(&c) {
+                bail!("corner {} is missing from the numbering", c.name());
+            }
+        }
+
+        Ok(Self {
+            reference,
+            direction,
+            numbering,
+        })
+    }
+
+    /// The convention of the original worksheet: U/D stickers are
+    /// orientation 0, twists count counter-clockwise and the corners are
+    /// numbered UFR, UFL, UBL, UBR, DBR, DBL, DFL, DFR.
+    pub fn worksheet() -> Self {
+        Self {
+            reference: Axis::Y,
+            direction: TwistDirection::CounterClockwise,
+            numbering: Corner::all().to_vec(),
+        }
+    }
+
+    pub fn reference(&self) -> Axis {
+        self.reference
+    }
+
+    pub fn direction(&self) -> TwistDirection {
+        self.direction
+    }
+
+    pub fn numbering(&self) -> &[Corner] {
+        &self.numbering
+    }
+
+    pub fn number_of(&self, c: Corner) -> usize {
+        self.numbering.iter().position(|&d| d == c).unwrap() + 1
+    }
+
+    pub fn corner_of(&self, i: usize) -> Corner {
+        self.numbering[i - 1]
+    }
+
+    fn steps(&self, from: usize, to: usize) -> Mod3 {
+        let cw = (to + 3 - from) % 3;
+        match self.direction {
+            TwistDirection::Clockwise => cw.into(),
+            TwistDirection::CounterClockwise => (3 - cw).into(),
+        }
+    }
+
+    fn decode_physical(&self, phys: &Physical) -> WreathElm {
+        let all = Corner::all();
+        let mut corr_book = vec![0; 8];
+        let mut vector = vec![Mod3::Zero; 8];
+        for (pos, &(piece, shift)) in all.iter().zip(phys.iter()) {
+            let q = self.number_of(*pos);
+            corr_book[self.number_of(piece) - 1] = q;
+            let landed = (piece.index_of(self.reference) + shift) % 3;
+            vector[q - 1] = self.steps(pos.index_of(self.reference), landed);
+        }
+        WreathElm::new(Replacement::from_correspond_book(&corr_book), vector)
+    }
+
+    fn encode_physical(&self, w: &WreathElm) -> Physical {
+        let r = w.get_replacement();
+        Corner::all()
+            .iter()
+            .map(|pos| {
+                let q = self.number_of(*pos);
+                let piece = self.corner_of(r.rev_find(q).unwrap_or(q));
+                let ori: usize = w.get_vector()[q - 1].into();
+                let cw = match self.direction {
+                    TwistDirection::Clockwise => ori,
+                    TwistDirection::CounterClockwise => 3 - ori,
+                };
+                let landed = (pos.index_of(self.reference) + cw) % 3;
+                let shift = (landed + 3 - piece.index_of(self.reference)) % 3;
+                (piece, shift)
+            })
+            .collect()
+    }
+
+    /// The state reached from solved by a clockwise quarter turn of `face`.
+    pub fn turn(&self, face: Face) -> WreathElm {
+        let n = face.normal();
+        let phys = Corner::all()
+            .iter()
+            .map(|pos| {
+                // the piece which arrives at `pos` comes from the inverse turn
+                let mut from = pos.position();
+                if dot(from, n) > 0 {
+                    for _ in 0..3 {
+                        from = face.turn(from);
+                    }
+                }
+                let piece = Corner::from_position(from);
+
+                // sticker with axis `a` of the piece ends on the turned axis
+                let a = piece.clockwise()[0];
+                let moved = if dot(from, n) > 0 {
+                    face.turn(unit(a, from[a as usize]))
+                } else {
+                    unit(a, from[a as usize])
+                };
+                let axis = Axis::from_index(moved.iter().position(|&x| x != 0).unwrap());
+                let shift = (pos.index_of(axis) + 3 - piece.index_of(a)) % 3;
+                (piece, shift)
+            })
+            .collect::<Physical>();
+        self.decode_physical(&phys)
+    }
+
+    /// The six face turns derived from this convention.
+    pub fn move_set(&self) -> MoveSet {
+        MoveSet::new(
+            Face::all()
+                .iter()
+                .map(|&face| Operation::new(face.name(), self.turn(face)))
+                .collect(),
+        )
+    }
+
+    /// Rewrites a state given in this convention in the convention `to`.
+    pub fn convert(&self, w: &WreathElm, to: &CubeConvention) -> WreathElm {
+        to.decode_physical(&self.encode_physical(w))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use crate::wreath::{decode, evaluate, identity_matrix};
+
+    // the face turns the worksheet started from, written out by hand
+    const WORKSHEET: [(Face, &str); 6] = [
+        (Face::U, "(1 2 3 4) [0 0 0 0 0 0 0 0]"),
+        (Face::D, "(5 6 7 8) [0 0 0 0 0 0 0 0]"),
+        (Face::L, "(2 7 6 3) [0 2 1 0 0 2 1 0]"),
+        (Face::R, "(1 4 5 8) [1 0 0 2 1 0 0 2]"),
+        (Face::F, "(1 8 7 2) [2 1 0 0 0 0 2 1]"),
+        (Face::B, "(3 6 5 4) [0 0 2 1 2 1 0 0]"),
+    ];
+
+    #[test]
+    fn default_turns_are_the_worksheet_ones() {
+        let convention = CubeConvention::default();
+        for (face, state) in WORKSHEET {
+            let expected = state.parse::<WreathElm>().unwrap();
+            assert_eq!(convention.turn(face), expected, "{}", face.name());
+        }
+        let moves = convention.move_set();
+        let names = moves
+            .generators()
+            .iter()
+            .map(|o| o.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["U", "D", "L", "R", "F", "B"]);
+    }
+
+    #[test]
+    fn convert_round_trip() {
+        let mut numbering = Corner::all().to_vec();
+        numbering.reverse();
+        let other = CubeConvention::new(Axis::X, TwistDirection::Clockwise, numbering).unwrap();
+        let worksheet = CubeConvention::worksheet();
+
+        let moves = worksheet.move_set();
+        let gens = moves.generators();
+        let mut rng = XorShift::new(3);
+        for _ in 0..20 {
+            let word = (0..20)
+                .map(|_| gens[rng.below(gens.len())].clone())
+                .collect::<Vec<_>>();
+            let w = decode(&evaluate(&word, &identity_matrix(8)));
+            let there = worksheet.convert(&w, &other);
+            assert_eq!(other.convert(&there, &worksheet), w);
+        }
+        // turns are the same physical moves in either convention
+        for face in Face::all() {
+            assert_eq!(
+                worksheet.convert(&worksheet.turn(face), &other),
+                other.turn(face)
+            );
+        }
+    }
+
+    #[test]
+    fn numbering_must_list_every_corner() {
+        let mut numbering = Corner::all().to_vec();
+        numbering[0] = Corner::DFR;
+        assert!(CubeConvention::new(Axis::Y, TwistDirection::Clockwise, numbering).is_err());
+        assert!(CubeConvention::new(Axis::Y, TwistDirection::Clockwise, vec![]).is_err());
+    }
+}
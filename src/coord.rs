@@ -0,0 +1,130 @@
+use crate::rotation::{from_seven, seven_corner_state, REFERENCE};
+use crate::wreath::WreathElm;
+use crate::{Mod3, Replacement};
+use anyhow::{bail, Result};
+
+// Coordinates of the 2x2x2 are taken in the 7-corner model, see `rotation`:
+// the arrangement of the seven corners by its Lehmer rank and their twists
+// in base 3, the last twist following from the others.
+//
+//   coordinate = permutation * TWISTS + twist
+
+/// Arrangements of the seven corners.
+pub const PERMUTATIONS: usize = 5040;
+/// Twists of the seven corners with total twist zero.
+pub const TWISTS: usize = 729;
+/// Legal states up to whole-cube rotation.
+pub const STATES: usize = PERMUTATIONS * TWISTS;
+
+/// Rank of the twists in base 3, the first twist most significant. The
+/// last entry is left out, it is implied when the total twist is zero.
+pub fn twist_rank(vector: &[Mod3]) -> usize {
+    let n = vector.len().saturating_sub(1);
+    vector[..n]
+        .iter()
+        .fold(0, |acc, &m| acc * 3 + usize::from(m))
+}
+
+/// The `n` twists with total twist zero of the given rank.
+pub fn twist_unrank(rank: usize, n: usize) -> Result<Vec<Mod3>> {
+    let count = 3usize.pow(n.saturating_sub(1) as u32);
+    if rank >= count {
+        bail!(
+            "rank {} is beyond the {} twists of {} pieces",
+            rank,
+            count,
+            n
+        );
+    }
+    let mut res = vec![Mod3::Zero; n];
+    let mut rest = rank;
+    for i in (0..n.saturating_sub(1)).rev() {
+        res[i] = Mod3::from(rest % 3);
+        rest /= 3;
+    }
+    if n > 0 {
+        res[n - 1] = res[..n - 1]
+            .iter()
+            .fold(Mod3::Zero, |acc, &m| acc + m)
+            .add_inv();
+    }
+    Ok(res)
+}
+
+/// The coordinate of a state of seven corners with total twist zero.
+pub fn seven_corner_coordinate(state: &WreathElm) -> Result<usize> {
+    let total = state
+        .get_vector()
+        .iter()
+        .fold(Mod3::Zero, |acc, &m| acc + m);
+    if state.size() != 7 || total != Mod3::Zero {
+        bail!("{} is no legal state of seven corners", state);
+    }
+    Ok(state.get_replacement().rank(7)? * TWISTS + twist_rank(state.get_vector()))
+}
+
+/// The state of seven corners at a coordinate.
+pub fn seven_corner_state_at(coordinate: usize) -> Result<WreathElm> {
+    if coordinate >= STATES {
+        bail!("coordinate {} is beyond the {} states", coordinate, STATES);
+    }
+    WreathElm::try_new(
+        Replacement::unrank(coordinate / TWISTS, 7)?,
+        twist_unrank(coordinate % TWISTS, 7)?,
+    )
+}
+
+/// The coordinate in `0..STATES` of a legal state of the 2x2x2. States
+/// which differ only by a whole-cube rotation share it.
+pub fn coordinate(state: &WreathElm) -> Result<usize> {
+    seven_corner_coordinate(&seven_corner_state(state)?)
+}
+
+/// The state at a coordinate, held with DBL solved.
+pub fn state_at(coordinate: usize) -> Result<WreathElm> {
+    from_seven(&seven_corner_state_at(coordinate)?, REFERENCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::MoveSet;
+    use crate::wreath::{decode, evaluate, identity_matrix};
+
+    #[test]
+    fn twist_rank_round_trip() {
+        for rank in 0..TWISTS {
+            let vector = twist_unrank(rank, 7).unwrap();
+            assert_eq!(twist_rank(&vector), rank);
+            assert_eq!(
+                vector.iter().fold(Mod3::Zero, |acc, &m| acc + m),
+                Mod3::Zero
+            );
+        }
+        assert!(twist_unrank(TWISTS, 7).is_err());
+    }
+
+    #[test]
+    fn coordinate_and_state_at_round_trip() {
+        assert_eq!(coordinate(&WreathElm::e(8)).unwrap(), 0);
+        for c in (0..STATES).step_by(9973).chain([STATES - 1]) {
+            assert_eq!(coordinate(&state_at(c).unwrap()).unwrap(), c);
+        }
+        assert!(state_at(STATES).is_err());
+    }
+
+    #[test]
+    fn coordinate_forgets_rotations() {
+        let moves = MoveSet::cube();
+        let state = |alg: &str| {
+            let word = moves.parse_word(alg).unwrap();
+            decode(&evaluate(&word, &identity_matrix(moves.size())))
+        };
+        // R L' turns the whole cube
+        assert_eq!(coordinate(&state("R L'")).unwrap(), 0);
+        assert_eq!(
+            coordinate(&state("R U F")).unwrap(),
+            coordinate(&state("R L' R U F")).unwrap()
+        );
+    }
+}
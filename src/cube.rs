@@ -0,0 +1,54 @@
+use crate::convention::CubeConvention;
+use crate::notation::parse_alg;
+use crate::wreath::Operation;
+use anyhow::Result;
+
+#[derive(Debug, Clone)]
+pub struct MoveSet {
+    opes: Vec<Operation>,
+}
+
+impl MoveSet {
+    pub fn new(opes: Vec<Operation>) -> Self {
+        Self { opes }
+    }
+
+    /// The six face turns of the 2x2x2 as used throughout the worksheet,
+    /// see `CubeConvention::worksheet`.
+    pub fn cube() -> Self {
+        CubeConvention::default().move_set()
+    }
+
+    pub fn size(&self) -> usize {
+        self.opes.first().map(|o| o.w.size()).unwrap_or(0)
+    }
+
+    pub fn generators(&self) -> &[Operation] {
+        &self.opes
+    }
+
+    /// Adds a generator, replacing one with the same name.
+    pub fn insert(&mut self, ope: Operation) {
+        match self.opes.iter_mut().find(|o| o.name == ope.name) {
+            Some(o) => *o = ope,
+            None => self.opes.push(ope),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Operation> {
+        self.opes.iter().find(|o| o.name == name)
+    }
+
+    /// The inverse of a generator or of the inverse of a generator.
+    pub fn inverse_of(&self, o: &Operation) -> Operation {
+        match o.name.strip_suffix("^{-1}").and_then(|base| self.get(base)) {
+            Some(base) => base.clone(),
+            None => o.rev(),
+        }
+    }
+
+    /// Parses an algorithm, see `notation::parse_alg`.
+    pub fn parse_word(&self, s: &str) -> Result<Vec<Operation>> {
+        parse_alg(self, s)
+    }
+}
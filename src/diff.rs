@@ -0,0 +1,181 @@
+use crate::convention::CubeConvention;
+use crate::facelet::FaceletCube;
+use crate::wreath::{decode, rotate, WreathElm};
+use crate::Mod3;
+use anyhow::{bail, Result};
+use std::fmt::{self, Display};
+
+/// Name of position `i`: the corner name on the 2x2x2, the number otherwise.
+pub fn position_name(i: usize, n: usize) -> String {
+    if n == 8 {
+        CubeConvention::default().corner_of(i).name().to_string()
+    } else {
+        i.to_string()
+    }
+}
+
+/// The difference between two states `a` and `b`.
+#[derive(Debug, Clone)]
+pub struct StateDiff {
+    /// `a^{-1} b`, so that `b = a * relative`.
+    pub relative: WreathElm,
+    /// Positions (1-based) holding a different piece or twist in `a` and `b`.
+    pub positions: Vec<usize>,
+}
+
+pub fn diff(a: &WreathElm, b: &WreathElm) -> Result<StateDiff> {
+    if a.size() != b.size() {
+        bail!(
+            "cannot compare states of {} and {} pieces",
+            a.size(),
+            b.size()
+        );
+    }
+    let relative = decode(&rotate(&b.get_matrix(), &a.rev()));
+    let positions = (1..=a.size())
+        .filter(|&q| {
+            a.piece_at(q) != b.piece_at(q) || a.get_vector()[q - 1] != b.get_vector()[q - 1]
+        })
+        .collect();
+    Ok(StateDiff {
+        relative,
+        positions,
+    })
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// The cycles of `relative` with named positions, e.g. `(UFR UFL UBL)`.
+    pub fn named_cycles(&self) -> Vec<String> {
+        let n = self.relative.size();
+        self.relative
+            .get_replacement()
+            .cycles()
+            .iter()
+            .map(|c| {
+                format!(
+                    "({})",
+                    c.iter()
+                        .map(|&i| position_name(i, n))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )
+            })
+            .collect()
+    }
+
+    /// The non-zero twists of `relative` by position.
+    pub fn twists(&self) -> Vec<(usize, Mod3)> {
+        self.relative
+            .get_vector()
+            .iter()
+            .enumerate()
+            .filter(|(_, &m)| m != Mod3::Zero)
+            .map(|(i, &m)| (i + 1, m))
+            .collect()
+    }
+}
+
+impl Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = self.relative.size();
+        let names = |qs: &[usize]| {
+            qs.iter()
+                .map(|&q| position_name(q, n))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        if self.is_empty() {
+            return write!(f, "the states are equal");
+        }
+
+        writeln!(f, "a^{{-1}} b: {}", self.relative)?;
+        writeln!(f, "cycles:    {}", self.named_cycles().join(""))?;
+        writeln!(
+            f,
+            "twists:    {}",
+            self.twists()
+                .iter()
+                .map(|&(q, m)| format!("{} by {}", position_name(q, n), usize::from(m)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        write!(f, "differ:    {}", names(&self.positions))
+    }
+}
+
+/// Both states position by position, differing rows marked with `*`.
+pub fn format_positions(a: &WreathElm, b: &WreathElm, d: &StateDiff) -> String {
+    let n = a.size();
+    let mut lines = vec![format!("  {:<9} {:<13} {:<13}", "position", "a", "b")];
+    for q in 1..=n {
+        let show = |w: &WreathElm| {
+            format!(
+                "{} twist {}",
+                position_name(w.piece_at(q), n),
+                usize::from(w.get_vector()[q - 1])
+            )
+        };
+        let mark = if d.positions.contains(&q) { '*' } else { ' ' };
+        lines.push(format!(
+            "{} {:<9} {:<13} {:<13}",
+            mark,
+            position_name(q, n),
+            show(a),
+            show(b)
+        ));
+    }
+    lines
+        .iter()
+        .map(|l| l.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The nets of both states next to each other, the stickers of differing
+/// corners in lower case.
+pub fn format_nets(a: &WreathElm, b: &WreathElm, d: &StateDiff) -> Result<String> {
+    let left = FaceletCube::from_wreath(a)?.net_marked(&d.positions);
+    let right = FaceletCube::from_wreath(b)?.net_marked(&d.positions);
+    let mut lines = vec![format!("{:<20}{}", "a", "b")];
+    for (l, r) in left.lines().zip(right.lines()) {
+        lines.push(format!("{:<20}{}", l, r));
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::{state_at, STATES};
+    use crate::rng::XorShift;
+
+    #[test]
+    fn relative_leads_from_a_to_b() {
+        let mut rng = XorShift::new(11);
+        for _ in 0..20 {
+            let a = state_at(rng.below(STATES)).unwrap();
+            let b = state_at(rng.below(STATES)).unwrap();
+            let d = diff(&a, &b).unwrap();
+            // b = a * relative as matrices
+            assert_eq!(
+                decode(&rotate(&d.relative.get_matrix(), &a.get_matrix())),
+                b
+            );
+            assert_eq!(d.is_empty(), a == b);
+            assert!(diff(&a, &a).unwrap().is_empty());
+            assert!(diff(&a, &a).unwrap().relative.is_e());
+        }
+    }
+
+    #[test]
+    fn positions_that_differ() {
+        let a = WreathElm::e(8);
+        let b = "(1 2) [0 0 1 0 0 0 0 2]".parse::<WreathElm>().unwrap();
+        assert_eq!(diff(&a, &b).unwrap().positions, vec![1, 2, 3, 8]);
+        assert!(diff(&a, &WreathElm::e(4)).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::wreath::WreathElm;
+use crate::{Mod3, Replacement};
+use anyhow::{anyhow, bail, Result};
+
+// Facelets are numbered face by face in the order U, L, F, R, B, D, each
+// face read like the usual net:
+//
+//           U0 U1
+//           U2 U3
+//     L0 L1 F0 F1 R0 R1 B0 B1
+//     L2 L3 F2 F3 R2 R3 B2 B3
+//           D0 D1
+//           D2 D3
+const U: usize = 0;
+const L: usize = 4;
+const F: usize = 8;
+const R: usize = 12;
+const B: usize = 16;
+const D: usize = 20;
+
+pub const FACELETS: usize = 24;
+pub const FACE_NAMES: [&str; 6] = ["U", "L", "F", "R", "B", "D"];
+
+// Clockwise quarter turns as 4-cycles of facelet locations.
+// The sticker at `c[0]` moves to `c[1]` and so on.
+const TURNS: [(&str, [[usize; 4]; 3]); 6] = [
+    (
+        "U",
+        [
+            [U, U + 1, U + 3, U + 2],
+            [F, L, B, R],
+            [F + 1, L + 1, B + 1, R + 1],
+        ],
+    ),
+    (
+        "D",
+        [
+            [D, D + 1, D + 3, D + 2],
+            [F + 2, R + 2, B + 2, L + 2],
+            [F + 3, R + 3, B + 3, L + 3],
+        ],
+    ),
+    (
+        "L",
+        [
+            [L, L + 1, L + 3, L + 2],
+            [U, F, D, B + 3],
+            [U + 2, F + 2, D + 2, B + 1],
+        ],
+    ),
+    (
+        "R",
+        [
+            [R, R + 1, R + 3, R + 2],
+            [F + 1, U + 1, B + 2, D + 1],
+            [F + 3, U + 3, B, D + 3],
+        ],
+    ),
+    (
+        "F",
+        [
+            [F, F + 1, F + 3, F + 2],
+            [U + 2, R, D + 1, L + 3],
+            [U + 3, R + 2, D, L + 1],
+        ],
+    ),
+    (
+        "B",
+        [
+            [B, B + 1, B + 3, B + 2],
+            [U, L + 2, D + 3, R + 1],
+            [U + 1, L, D + 2, R + 3],
+        ],
+    ),
+];
+
+// Facelets of corners 1..8 (UFR, UFL, UBL, UBR, DBR, DBL, DFL, DFR), each
+// starting from the U/D sticker and going counter-clockwise seen from outside.
+const CORNERS: [[usize; 3]; 8] = [
+    [U + 3, F + 1, R],
+    [U + 2, L + 1, F],
+    [U, B + 1, L],
+    [U + 1, R + 1, B],
+    [D + 3, B + 2, R + 3],
+    [D + 2, L + 2, B + 3],
+    [D, F + 2, L + 3],
+    [D + 1, R + 2, F + 3],
+];
+
+/// A 2x2x2 simulated at the level of its 24 stickers.
+/// `facelets[i]` is the home location of the sticker now at location `i`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaceletCube {
+    facelets: [usize; FACELETS],
+}
+
+impl Default for FaceletCube {
+    fn default() -> Self {
+        Self::solved()
+    }
+}
+
+impl FaceletCube {
+    pub fn solved() -> Self {
+        let mut facelets = [0; FACELETS];
+        for (i, f) in facelets.iter_mut().enumerate() {
+            *f = i;
+        }
+        Self { facelets }
+    }
+
+    /// The sticker arrangement of a state given in the worksheet's convention.
+    pub fn from_wreath(w: &WreathElm) -> Result<Self> {
+        if w.size() != CORNERS.len() {
+            bail!("expected {} corners, got {}", CORNERS.len(), w.size());
+        }
+
+        let mut facelets = [0; FACELETS];
+        for (q, pos) in CORNERS.iter().enumerate() {
+            let p = w.get_replacement().rev_find(q + 1).unwrap_or(q + 1) - 1;
+            let k: usize = w.get_vector()[q].into();
+            for (j, &sticker) in CORNERS[p].iter().enumerate() {
+                facelets[pos[(k + j) % 3]] = sticker;
+            }
+        }
+        Ok(Self { facelets })
+    }
+
+    /// The cube with the given `facelets`, not checked to be reachable.
+    pub fn from_facelets(facelets: [usize; FACELETS]) -> Self {
+        Self { facelets }
+    }
+
+    pub fn facelets(&self) -> &[usize; FACELETS] {
+        &self.facelets
+    }
+
+    /// The face (`U`, `L`, ...) each sticker belongs to, i.e. its colour.
+    pub fn colors(&self) -> [&'static str; FACELETS] {
+        let mut res = [""; FACELETS];
+        for (c, &f) in res.iter_mut().zip(self.facelets.iter()) {
+            *c = FACE_NAMES[f / 4];
+        }
+        res
+    }
+
+    /// Unfolded picture of the cube, one letter per sticker colour.
+    pub fn net(&self) -> String {
+        self.net_marked(&[])
+    }
+
+    /// Like `net`, but the stickers of the corners at the given positions
+    /// (1..=8) are written in lower case.
+    pub fn net_marked(&self, corners: &[usize]) -> String {
+        let mut colors = self.colors().map(|c| c.to_string());
+        for &q in corners.iter() {
+            for &i in CORNERS[q - 1].iter() {
+                colors[i] = colors[i].to_lowercase();
+            }
+        }
+        let row = |idx: &[usize]| {
+            idx.iter()
+                .map(|&i| colors[i].as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        [
+            format!("    {}", row(&[U, U + 1])),
+            format!("    {}", row(&[U + 2, U + 3])),
+            row(&[L, L + 1, F, F + 1, R, R + 1, B, B + 1]),
+            row(&[L + 2, L + 3, F + 2, F + 3, R + 2, R + 3, B + 2, B + 3]),
+            format!("    {}", row(&[D, D + 1])),
+            format!("    {}", row(&[D + 2, D + 3])),
+        ]
+        .join("\n")
+    }
+
+    /// Physically turns `face` clockwise `times` quarter turns.
+    pub fn turn(&mut self, face: &str, times: usize) -> Result<()> {
+        let cycles = TURNS
+            .iter()
+            .find(|(name, _)| *name == face)
+            .map(|(_, cycles)| cycles)
+            .ok_or_else(|| anyhow!("unknown face `{}`", face))?;
+
+        for _ in 0..(times % 4) {
+            let old = self.facelets;
+            for c in cycles.iter() {
+                for k in 0..4 {
+                    self.facelets[c[(k + 1) % 4]] = old[c[k]];
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a move given by operation name (`U` or `U^{-1}`).
+    pub fn apply(&mut self, name: &str) -> Result<()> {
+        match name.strip_suffix("^{-1}") {
+            Some(face) => self.turn(face, 3),
+            None => self.turn(name, 1),
+        }
+    }
+
+    /// Applies a word in the same order as `evaluate`, i.e. the last
+    /// operation of the word acts first.
+    pub fn apply_word(&mut self, names: &[String]) -> Result<()> {
+        for name in names.iter().rev() {
+            self.apply(name)?;
+        }
+        Ok(())
+    }
+
+    /// Reads off the corner permutation and twists in the worksheet's
+    /// convention.
+    pub fn to_wreath(&self) -> Result<WreathElm> {
+        let mut corr_book = vec![0; CORNERS.len()];
+        let mut vector = vec![Mod3::Zero; CORNERS.len()];
+
+        for (q, pos) in CORNERS.iter().enumerate() {
+            let stickers = pos.iter().map(|&i| self.facelets[i]).collect::<Vec<_>>();
+            let p = CORNERS
+                .iter()
+                .position(|home| stickers.iter().all(|s| home.contains(s)))
+                .ok_or_else(|| anyhow!("stickers {:?} do not form a corner", stickers))?;
+            let k = stickers.iter().position(|&s| s == CORNERS[p][0]).unwrap();
+            corr_book[p] = q + 1;
+            vector[q] = k.into();
+        }
+
+        Ok(WreathElm::new(
+            Replacement::from_correspond_book(&corr_book),
+            vector,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::MoveSet;
+    use crate::rng::XorShift;
+    use crate::wreath::{decode, evaluate, identity_matrix};
+
+    // Random words evaluated both on stickers and through `rotate`/`decode`
+    // must give the same state.
+    #[test]
+    fn stickers_agree_with_the_wreath_model() {
+        let moves = MoveSet::cube();
+        let gens = moves.generators();
+        let mut rng = XorShift::new(0);
+
+        for _ in 0..100 {
+            let word = (0..30)
+                .map(|_| {
+                    let o = &gens[rng.below(gens.len())];
+                    if rng.below(2) == 0 {
+                        o.clone()
+                    } else {
+                        o.rev()
+                    }
+                })
+                .collect::<Vec<_>>();
+            let names = word.iter().map(|o| o.name.clone()).collect::<Vec<_>>();
+
+            let expected = decode(&evaluate(&word, &identity_matrix(moves.size())));
+
+            let mut cube = FaceletCube::solved();
+            cube.apply_word(&names).unwrap();
+            assert_eq!(cube.to_wreath().unwrap(), expected, "{}", names.join(" "));
+        }
+    }
+
+    #[test]
+    fn solved_cube_is_the_identity() {
+        let w = FaceletCube::solved().to_wreath().unwrap();
+        assert_eq!(w, decode(&identity_matrix(w.size())));
+    }
+}
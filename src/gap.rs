@@ -0,0 +1,239 @@
+use crate::cube::MoveSet;
+use crate::facelet::{FaceletCube, FACELETS};
+use crate::wreath::WreathElm;
+use crate::{Mod3, Replacement};
+use anyhow::{anyhow, bail, Result};
+use std::fmt::Write;
+
+// GAP multiplies permutations from left to right, so the word `R U` of this
+// crate, where `U` acts first, is `U*R` in GAP. The group generated by the
+// moves is the same either way.
+
+/// The permutation action a state is exported as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapRepresentation {
+    /// On the 24 stickers of the 2x2x2, numbered as in `facelet`.
+    Facelets,
+    /// On the `3n` points `3(p - 1) + o + 1` for piece `p` with orientation
+    /// `o`, i.e. inside `WreathProduct(CyclicGroup(IsPermGroup, 3),
+    /// SymmetricGroup(n))`.
+    Wreath,
+}
+
+fn gap_cycles(r: &Replacement) -> String {
+    if r.cycles().is_empty() {
+        return "()".to_string();
+    }
+    r.cycles()
+        .iter()
+        .map(|c| {
+            format!(
+                "({})",
+                c.iter()
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )
+        })
+        .collect()
+}
+
+/// Writes `w` as a GAP permutation literal such as `(1,2,3)(4,5)`.
+pub fn gap_permutation(w: &WreathElm, rep: GapRepresentation) -> Result<String> {
+    let images = match rep {
+        GapRepresentation::Facelets => {
+            let cube = FaceletCube::from_wreath(w)?;
+            let mut images = vec![0; FACELETS];
+            for (i, &home) in cube.facelets().iter().enumerate() {
+                images[home] = i + 1;
+            }
+            images
+        }
+        GapRepresentation::Wreath => {
+            let n = w.size();
+            let mut images = vec![0; 3 * n];
+            for p in 1..=n {
+                let q = w.get_replacement().replace(p);
+                let twist: usize = w.get_vector()[q - 1].into();
+                for o in 0..3 {
+                    images[3 * (p - 1) + o] = 3 * (q - 1) + (o + twist) % 3 + 1;
+                }
+            }
+            images
+        }
+    };
+    Ok(gap_cycles(&Replacement::from_correspond_book(&images)))
+}
+
+/// Reads back a permutation in the given representation as a state with `n`
+/// pieces, failing if it is not one.
+pub fn state_from_gap(r: &Replacement, rep: GapRepresentation, n: usize) -> Result<WreathElm> {
+    let w = match rep {
+        GapRepresentation::Facelets => {
+            if n != 8 || r.get_k() > FACELETS {
+                bail!("the facelets only describe the 8 corners of the 2x2x2");
+            }
+            let mut facelets = [0; FACELETS];
+            for (home, q) in r.images(FACELETS).into_iter().enumerate() {
+                facelets[q - 1] = home;
+            }
+            FaceletCube::from_facelets(facelets).to_wreath()?
+        }
+        GapRepresentation::Wreath => {
+            if r.get_k() > 3 * n {
+                bail!(
+                    "point {} is beyond the {} points of C3 wr S{}",
+                    r.get_k(),
+                    3 * n,
+                    n
+                );
+            }
+            let mut corr_book = vec![0; n];
+            let mut vector = vec![Mod3::Zero; n];
+            for p in 1..=n {
+                let image = r.replace(3 * (p - 1) + 1) - 1;
+                let (q, twist) = (image / 3 + 1, image % 3);
+                corr_book[p - 1] = q;
+                vector[q - 1] = twist.into();
+            }
+            WreathElm::try_new(Replacement::from_images(&corr_book)?, vector)?
+        }
+    };
+    if gap_permutation(&w, rep)? != gap_cycles(r) {
+        bail!("{} is not a state of {} pieces", gap_cycles(r), n);
+    }
+    Ok(w)
+}
+
+/// A GAP script defining the group generated by `moves`, ending with
+/// `Size(G);` so that the order can be compared.
+pub fn export_group(moves: &MoveSet, rep: GapRepresentation) -> Result<String> {
+    let gens = moves.generators();
+    if gens.is_empty() {
+        bail!("no generators to export");
+    }
+
+    let mut res = String::new();
+    match rep {
+        GapRepresentation::Facelets => {
+            writeln!(res, "# the moves on the {} facelets", FACELETS).unwrap();
+        }
+        GapRepresentation::Wreath => {
+            writeln!(res, "# the moves inside C3 wr S{}", moves.size()).unwrap();
+        }
+    }
+    writeln!(
+        res,
+        "names := [{}];",
+        gens.iter()
+            .map(|o| format!("\"{}\"", o.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+    .unwrap();
+    writeln!(res, "gens := [").unwrap();
+    for (i, o) in gens.iter().enumerate() {
+        let sep = if i + 1 < gens.len() { "," } else { "" };
+        writeln!(
+            res,
+            "  {}{}  # {}",
+            gap_permutation(&o.w, rep)?,
+            sep,
+            o.name
+        )
+        .unwrap();
+    }
+    writeln!(res, "];").unwrap();
+    if rep == GapRepresentation::Wreath {
+        writeln!(
+            res,
+            "W := WreathProduct(CyclicGroup(IsPermGroup, 3), SymmetricGroup({}));",
+            moves.size()
+        )
+        .unwrap();
+    }
+    writeln!(res, "G := GroupWithGenerators(gens);").unwrap();
+    if rep == GapRepresentation::Wreath {
+        writeln!(res, "IsSubgroup(W, G);").unwrap();
+    }
+    writeln!(res, "Size(G);").unwrap();
+    Ok(res)
+}
+
+/// Parses a GAP permutation literal such as `(1,2,3)(4,5)` or `()`. As in
+/// GAP, cycles which are not disjoint are multiplied from left to right, and
+/// the line continuations of GAP's output are allowed. Only GAP's syntax is
+/// accepted, so points are separated by commas and there is no `e`. Points
+/// must lie in `1..=n`.
+pub fn parse_gap_permutation(s: &str, n: usize) -> Result<Replacement> {
+    let s = s.replace("\\\n", "");
+    let s = s.trim();
+    if s == "()" {
+        return Ok(Replacement::e());
+    }
+    let mut cycles = vec![];
+    let mut rest = s;
+    while !rest.is_empty() {
+        let inner = rest
+            .strip_prefix('(')
+            .ok_or_else(|| anyhow!("expected `(` in GAP permutation `{}`", s))?;
+        let end = inner
+            .find(')')
+            .ok_or_else(|| anyhow!("missing `)` in GAP permutation `{}`", s))?;
+        let cycle = inner[..end]
+            .split(',')
+            .map(|t| {
+                t.trim()
+                    .parse::<usize>()
+                    .map_err(|_| anyhow!("`{}` is not a point of a GAP cycle", t.trim()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        cycles.push(cycle);
+        rest = inner[end + 1..].trim_start();
+    }
+    // `Replacement::new` applies the last cycle first
+    cycles.reverse();
+    Ok(Replacement::from_cycles(cycles, n)?.rearrange())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wreath::{decode, evaluate, identity_matrix};
+
+    #[test]
+    fn cycles_multiply_left_to_right() {
+        let r = parse_gap_permutation("(1,2)(2,3)", 3).unwrap();
+        assert_eq!(r.to_string(), "(1 3 2)");
+        assert_eq!(parse_gap_permutation("()", 3).unwrap(), Replacement::e());
+        assert_eq!(
+            parse_gap_permutation("(1,2,\\\n3)", 3).unwrap().to_string(),
+            "(1 2 3)"
+        );
+        assert!(parse_gap_permutation("1,2", 3).is_err());
+        assert!(parse_gap_permutation("(1,4)", 3).is_err());
+        // the crate's own syntax is not GAP's
+        assert!(parse_gap_permutation("e", 3).is_err());
+        assert!(parse_gap_permutation("(1 2)", 3).is_err());
+        assert!(parse_gap_permutation("(1,,2)", 3).is_err());
+    }
+
+    #[test]
+    fn states_round_trip() {
+        let moves = MoveSet::cube();
+        let word = moves.parse_word("R U' F2 D L'").unwrap();
+        let w = decode(&evaluate(&word, &identity_matrix(moves.size())));
+        for rep in [GapRepresentation::Facelets, GapRepresentation::Wreath] {
+            let r = parse_gap_permutation(&gap_permutation(&w, rep).unwrap(), 24).unwrap();
+            assert_eq!(state_from_gap(&r, rep, 8).unwrap(), w);
+        }
+    }
+
+    #[test]
+    fn non_states_are_rejected() {
+        // two stickers swapped, or two orientations of one corner
+        let r = parse_gap_permutation("(1,2)", 24).unwrap();
+        assert!(state_from_gap(&r, GapRepresentation::Facelets, 8).is_err());
+        assert!(state_from_gap(&r, GapRepresentation::Wreath, 8).is_err());
+    }
+}
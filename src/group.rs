@@ -0,0 +1,259 @@
+use crate::cube::MoveSet;
+use crate::notation::format_word;
+use crate::solver::{Phase, Solution, Step};
+use crate::wreath::{decode, evaluate, Operation, WreathElm};
+use crate::{solve_combination, Mod3, Mod3Matrix, Mod3Vector, Replacement};
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
+
+// One node of the breadth-first tree over the permutations: the move which
+// reached it from its parent and the twists of the element reached that way.
+#[derive(Debug, Clone)]
+struct Node {
+    parent: Option<(Vec<usize>, usize)>,
+    vector: Vec<Mod3>,
+}
+
+/// The group generated by a move set, split into its permutation part and
+/// its pure twists.
+///
+/// The reachable permutations are explored breadth first, so that every one
+/// of them comes with a shortest word. The elements with trivial permutation
+/// form a subspace of the twist vectors, spanned by the Schreier generators
+/// `w_q^{-1} s w_p` of that tree. This works for any puzzle whose pieces only
+/// have a few thousand arrangements, such as the ones in `puzzles/`.
+#[derive(Debug, Clone)]
+pub struct PuzzleGroup {
+    size: usize,
+    opes: Vec<Operation>,
+    tree: HashMap<Vec<usize>, Node>,
+    twists: Vec<(Vec<Mod3>, Vec<Operation>)>,
+}
+
+// twists of `a^{-1} b` for two elements with the same permutation
+fn difference(perm: &[usize], a: &[Mod3], b: &[Mod3]) -> Vec<Mod3> {
+    perm.iter().map(|&q| b[q - 1] - a[q - 1]).collect()
+}
+
+impl PuzzleGroup {
+    pub fn new(moves: &MoveSet) -> Self {
+        let n = moves.size();
+        let mut opes = moves.generators().to_vec();
+        opes.extend(moves.generators().iter().map(|o| moves.inverse_of(o)));
+
+        let perms = opes
+            .iter()
+            .map(|o| o.w.get_replacement().images(n))
+            .collect::<Vec<_>>();
+        let inverses = perms
+            .iter()
+            .map(|p| {
+                let mut inv = vec![0; n];
+                for (i, &q) in p.iter().enumerate() {
+                    inv[q - 1] = i + 1;
+                }
+                inv
+            })
+            .collect::<Vec<_>>();
+
+        let start = (1..=n).collect::<Vec<_>>();
+        let mut tree = HashMap::new();
+        tree.insert(
+            start.clone(),
+            Node {
+                parent: None,
+                vector: vec![Mod3::Zero; n],
+            },
+        );
+        let mut layer = vec![start];
+        let mut schreier = vec![];
+        let mut seen = HashSet::new();
+
+        while !layer.is_empty() {
+            let mut next_layer = vec![];
+            for p in layer {
+                let vector = tree[&p].vector.clone();
+                for (si, s) in perms.iter().enumerate() {
+                    // left multiplication by the move: it acts after `p`
+                    let q = p.iter().map(|&x| s[x - 1]).collect::<Vec<_>>();
+                    let v = (0..n)
+                        .map(|i| opes[si].w.get_vector()[i] + vector[inverses[si][i] - 1])
+                        .collect::<Vec<_>>();
+                    match tree.get(&q) {
+                        Some(node) => {
+                            let h = difference(&q, &node.vector, &v);
+                            if h.iter().any(|&m| m != Mod3::Zero) && seen.insert(h.clone()) {
+                                schreier.push((h, q.clone(), si, p.clone()));
+                            }
+                        }
+                        None => {
+                            tree.insert(
+                                q.clone(),
+                                Node {
+                                    parent: Some((p.clone(), si)),
+                                    vector: v,
+                                },
+                            );
+                            next_layer.push(q);
+                        }
+                    }
+                }
+            }
+            layer = next_layer;
+        }
+
+        let mut group = Self {
+            size: n,
+            opes,
+            tree,
+            twists: vec![],
+        };
+
+        // keep the Schreier generators which enlarge the span
+        for (h, q, si, p) in schreier {
+            let mut columns = group
+                .twists
+                .iter()
+                .map(|(v, _)| Mod3Vector::from(v.as_slice()))
+                .collect::<Vec<_>>();
+            columns.push(Mod3Vector::from(h.as_slice()));
+            if Mod3Matrix::from_columns(&columns).rank() == columns.len() {
+                let mut word = group.inverse_word(&group.word(&q));
+                word.push(group.opes[si].clone());
+                word.extend(group.word(&p));
+                group.twists.push((h, word));
+            }
+            if group.twists.len() == n {
+                break;
+            }
+        }
+        group
+    }
+
+    // written word of the tree element with permutation `p`
+    fn word(&self, p: &[usize]) -> Vec<Operation> {
+        let mut res = vec![];
+        let mut cur = p.to_vec();
+        while let Some((parent, si)) = self.tree[&cur].parent.clone() {
+            res.push(self.opes[si].clone());
+            cur = parent;
+        }
+        res
+    }
+
+    fn inverse_word(&self, w: &[Operation]) -> Vec<Operation> {
+        let n = self.opes.len() / 2;
+        w.iter()
+            .rev()
+            .map(|o| {
+                let i = self.opes.iter().position(|p| p.name == o.name).unwrap();
+                self.opes[(i + n) % (2 * n)].clone()
+            })
+            .collect()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of reachable arrangements of the pieces.
+    pub fn permutations(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Dimension over Z/3 of the twists reachable without moving any piece.
+    pub fn twist_rank(&self) -> usize {
+        self.twists.len()
+    }
+
+    /// Order of the group, i.e. the number of reachable states.
+    pub fn order(&self) -> u128 {
+        self.permutations() as u128 * 3u128.pow(self.twist_rank() as u32)
+    }
+
+    // permutation images and the twists left after undoing the tree element
+    fn split(&self, state: &WreathElm) -> Option<(Vec<usize>, Vec<Mod3>)> {
+        let perm = state.get_replacement().images(self.size);
+        let node = self.tree.get(&perm)?;
+        let rest = difference(&perm, &node.vector, state.get_vector());
+        Some((perm, rest))
+    }
+
+    fn combination(&self, rest: &[Mod3]) -> Option<Vec<Mod3>> {
+        let basis = self
+            .twists
+            .iter()
+            .map(|(v, _)| v.clone())
+            .collect::<Vec<_>>();
+        let target = rest.iter().map(|m| m.add_inv()).collect::<Vec<_>>();
+        solve_combination(&basis, &target)
+    }
+
+    /// Whether some reachable state arranges the pieces as `r`.
+    pub fn contains_permutation(&self, r: &Replacement) -> bool {
+        r.get_correct_k().is_none_or(|k| k <= self.size)
+            && self.tree.contains_key(&r.images(self.size))
+    }
+
+    pub fn contains(&self, state: &WreathElm) -> bool {
+        state.size() == self.size
+            && self
+                .split(state)
+                .is_some_and(|(_, rest)| self.combination(&rest).is_some())
+    }
+
+    /// Solves `state` by undoing its permutation along the tree and then
+    /// cancelling the remaining twist with the pure twists.
+    pub fn solve(&self, state: &WreathElm) -> Result<Solution> {
+        if state.size() != self.size {
+            bail!("state has {} pieces, expected {}", state.size(), self.size);
+        }
+        let (perm, rest) = match self.split(state) {
+            Some(split) => split,
+            None => bail!("illegal state: the pieces cannot be arranged like this"),
+        };
+        let coeffs = match self.combination(&rest) {
+            Some(coeffs) => coeffs,
+            None => bail!("illegal state: twist cannot be solved"),
+        };
+
+        let mut steps = vec![];
+        for ((v, word), &c) in self.twists.iter().zip(coeffs.iter()) {
+            let name = format!(
+                "[{}]",
+                v.iter()
+                    .map(|&m| usize::from(m).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+            for _ in 0..usize::from(c) {
+                steps.push(Step {
+                    phase: Phase::Twist,
+                    name: name.clone(),
+                    label: format_word(word),
+                    word: word.clone(),
+                });
+            }
+        }
+        let undo = self.inverse_word(&self.word(&perm));
+        if !undo.is_empty() {
+            let mut back = vec![0; self.size];
+            for (i, &q) in perm.iter().enumerate() {
+                back[q - 1] = i + 1;
+            }
+            steps.push(Step {
+                phase: Phase::Permutation,
+                name: Replacement::from_correspond_book(&back).to_string(),
+                label: format_word(&undo),
+                word: undo,
+            });
+        }
+        let solution = Solution { steps };
+
+        let res = decode(&evaluate(&solution.operations(), &state.get_matrix()));
+        if !res.is_e() {
+            bail!("solution does not solve the state: {}", res);
+        }
+        Ok(solution)
+    }
+}
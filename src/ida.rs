@@ -0,0 +1,691 @@
+use crate::cube::MoveSet;
+use crate::wreath::{Operation, WreathElm};
+use crate::{Mod3, Replacement};
+use anyhow::{bail, Result};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Iterative deepening A*: depth-first searches bounded by the number of moves
+// made plus a lower bound on the moves still needed, the bound growing until
+// solutions appear. The lower bounds come from pattern databases, complete
+// distance tables of a projection of the puzzle: where some of the pieces
+// are, or the twists alone. A move of the puzzle is a move of the
+// projection, so these distances never overestimate and every solution is
+// found at its true length.
+//
+// States are kept as in `setup`: `perm[p - 1]` is the position of piece p and
+// `twist[q - 1]` the twist at position q.
+
+// bound on the entries of a pattern database
+const MAX_ENTRIES: usize = 1 << 26;
+const UNSEEN: u8 = u8::MAX;
+
+/// How the length of a solution is counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Every generator and its inverse count one move.
+    Quarter,
+    /// Every power of a generator counts one move.
+    Face,
+}
+
+/// A lower bound on the number of moves solving a state.
+pub trait Heuristic {
+    fn estimate(&self, perm: &[usize], twist: &[Mod3]) -> usize;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Elm {
+    perm: Vec<usize>,
+    twist: Vec<Mod3>,
+}
+
+impl Elm {
+    fn from_wreath(w: &WreathElm) -> Self {
+        Self {
+            perm: w.get_replacement().images(w.size()),
+            twist: w.get_vector().to_vec(),
+        }
+    }
+
+    fn to_wreath(&self) -> WreathElm {
+        WreathElm::new(
+            Replacement::from_images(&self.perm).unwrap(),
+            self.twist.clone(),
+        )
+    }
+
+    // `self * other` written into `res`, other acting first
+    fn mul_into(&self, other: &Self, res: &mut Self) {
+        res.twist.copy_from_slice(&self.twist);
+        for (p, &q) in other.perm.iter().enumerate() {
+            res.perm[p] = self.perm[q - 1];
+            res.twist[self.perm[q - 1] - 1] += other.twist[q - 1];
+        }
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let mut res = self.clone();
+        self.mul_into(other, &mut res);
+        res
+    }
+
+    fn is_e(&self) -> bool {
+        self.perm.iter().enumerate().all(|(i, &q)| q == i + 1)
+            && self.twist.iter().all(|&m| m == Mod3::Zero)
+    }
+}
+
+// one move of the search: a power of generator `axis`
+#[derive(Debug, Clone)]
+struct Move {
+    ope: Operation,
+    elm: Elm,
+    axis: usize,
+    power: usize,
+    order: usize,
+}
+
+// every reachable value of `key` from the solved state, by `step` for each
+// move, with its distance; `index` numbers the values below `entries`
+fn distances<K: Clone>(
+    start: K,
+    entries: usize,
+    index: impl Fn(&K) -> usize,
+    moves: &[Operation],
+    step: impl Fn(&Elm, &K) -> K,
+) -> Vec<u8> {
+    let moves = moves
+        .iter()
+        .map(|o| Elm::from_wreath(&o.w))
+        .collect::<Vec<_>>();
+    let mut table = vec![UNSEEN; entries];
+    table[index(&start)] = 0;
+    let mut queue = VecDeque::from([start]);
+    while let Some(key) = queue.pop_front() {
+        let d = table[index(&key)];
+        for m in moves.iter() {
+            let next = step(m, &key);
+            let i = index(&next);
+            if table[i] == UNSEEN {
+                // far distances are cut below UNSEEN, still a lower bound
+                table[i] = (d + 1).min(UNSEEN - 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    table
+}
+
+/// Distances of the arrangements of some pieces, ignoring all twists and
+/// the other pieces.
+#[derive(Debug, Clone)]
+pub struct PermutationDatabase {
+    n: usize,
+    pieces: Vec<usize>,
+    table: Vec<u8>,
+}
+
+// rank of distinct positions among the arrangements of `positions.len()` of
+// `n` positions
+fn partial_rank(positions: &[usize], n: usize) -> usize {
+    let mut res = 0;
+    for (i, &q) in positions.iter().enumerate() {
+        let smaller_used = positions[..i].iter().filter(|&&r| r < q).count();
+        res = res * (n - i) + (q - 1 - smaller_used);
+    }
+    res
+}
+
+impl PermutationDatabase {
+    /// The table for `pieces` under `moves`, which should be those of the
+    /// search, see `Search::moves`.
+    pub fn new(moves: &[Operation], pieces: &[usize]) -> Result<Self> {
+        let n = moves.first().map(|o| o.w.size()).unwrap_or(0);
+        if pieces.iter().any(|&p| p == 0 || p > n) {
+            bail!("the pieces are numbered 1..={}", n);
+        }
+        if (1..pieces.len()).any(|i| pieces[..i].contains(&pieces[i])) {
+            bail!("a piece is listed twice");
+        }
+        let entries = (n - pieces.len() + 1..=n)
+            .try_fold(1usize, |acc, k| acc.checked_mul(k))
+            .filter(|&e| e <= MAX_ENTRIES);
+        let entries = match entries {
+            Some(e) => e,
+            None => bail!("too many arrangements of {} pieces", pieces.len()),
+        };
+        let table = distances(
+            pieces.to_vec(),
+            entries,
+            |positions| partial_rank(positions, n),
+            moves,
+            |m, positions| positions.iter().map(|&q| m.perm[q - 1]).collect(),
+        );
+        Ok(Self {
+            n,
+            pieces: pieces.to_vec(),
+            table,
+        })
+    }
+
+    /// Pieces `1..=k` for the largest `k` whose table stays below `entries`.
+    pub fn largest(moves: &[Operation], entries: usize) -> Result<Self> {
+        let n = moves.first().map(|o| o.w.size()).unwrap_or(0);
+        let mut k = 0;
+        let mut size = 1usize;
+        while k < n && size.saturating_mul(n - k) <= entries.min(MAX_ENTRIES) {
+            size *= n - k;
+            k += 1;
+        }
+        Self::new(moves, &(1..=k).collect::<Vec<_>>())
+    }
+
+    pub fn pieces(&self) -> &[usize] {
+        &self.pieces
+    }
+}
+
+impl Heuristic for PermutationDatabase {
+    fn estimate(&self, perm: &[usize], _: &[Mod3]) -> usize {
+        let positions = self.pieces.iter().map(|&p| perm[p - 1]).collect::<Vec<_>>();
+        self.table[partial_rank(&positions, self.n)] as usize
+    }
+}
+
+/// Distances of the twists of all positions, ignoring where the pieces are.
+#[derive(Debug, Clone)]
+pub struct OrientationDatabase {
+    table: Vec<u8>,
+}
+
+fn twist_index(twist: &[Mod3]) -> usize {
+    twist.iter().fold(0, |acc, &m| acc * 3 + usize::from(m))
+}
+
+impl OrientationDatabase {
+    /// The table under `moves`, which should be those of the search.
+    pub fn new(moves: &[Operation]) -> Result<Self> {
+        let n = moves.first().map(|o| o.w.size()).unwrap_or(0);
+        let entries = match 3usize.checked_pow(n as u32) {
+            Some(e) if e <= MAX_ENTRIES => e,
+            _ => bail!("too many twists of {} pieces", n),
+        };
+        let table = distances(
+            vec![Mod3::Zero; n],
+            entries,
+            |twist| twist_index(twist),
+            moves,
+            |m, twist| {
+                // the twist at q moves on to where the move takes q
+                let mut next = m.twist.clone();
+                for (q, &v) in twist.iter().enumerate() {
+                    next[m.perm[q] - 1] += v;
+                }
+                next
+            },
+        );
+        Ok(Self { table })
+    }
+}
+
+impl Heuristic for OrientationDatabase {
+    fn estimate(&self, _: &[usize], twist: &[Mod3]) -> usize {
+        self.table[twist_index(twist)] as usize
+    }
+}
+
+/// When to give up. Without any limit the search only ends through the
+/// callback of `Search::solve`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    pub max_length: Option<usize>,
+    pub max_nodes: Option<u64>,
+    pub max_time: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every solution up to `Limits::max_length` was reported, or every
+    /// solution at all if the moves only allow finitely many sequences.
+    Exhausted,
+    /// The callback asked to stop.
+    Stopped,
+    NodeLimit,
+    TimeLimit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    pub outcome: Outcome,
+    /// States visited.
+    pub nodes: u64,
+    /// Every solution of at most this length was reported.
+    pub length: Option<usize>,
+}
+
+/// An IDA* search over the moves of a puzzle.
+pub struct Search {
+    metric: Metric,
+    moves: Vec<Move>,
+    // whether the generators commute
+    commute: Vec<Vec<bool>>,
+    heuristics: Vec<Box<dyn Heuristic>>,
+}
+
+struct Run<'a> {
+    limits: Limits,
+    start: Instant,
+    nodes: u64,
+    stop: Option<Outcome>,
+    next_bound: usize,
+    states: Vec<Elm>,
+    path: Vec<usize>,
+    on_solution: &'a mut dyn FnMut(&[Operation]) -> bool,
+}
+
+impl Search {
+    /// The moves of `moves` in the given metric, without heuristics; add
+    /// them with `with_heuristic`.
+    pub fn new(moves: &MoveSet, metric: Metric) -> Self {
+        let gens = moves.generators();
+        let mut res = vec![];
+        for (axis, g) in gens.iter().enumerate() {
+            let order = g.w.order();
+            let powers = match metric {
+                Metric::Quarter => vec![1, order - 1],
+                Metric::Face => (1..order).collect(),
+            };
+            let base = Elm::from_wreath(&g.w);
+            let mut power = base.clone();
+            for k in 1..order {
+                if powers.contains(&k) {
+                    let ope = match k {
+                        1 => g.clone(),
+                        k if k == order - 1 => moves.inverse_of(g),
+                        k => Operation::new(&format!("{}{}", g.name, k), power.to_wreath()),
+                    };
+                    res.push(Move {
+                        ope,
+                        elm: power.clone(),
+                        axis,
+                        power: k,
+                        order,
+                    });
+                }
+                power = base.mul(&power);
+            }
+        }
+        let commute = gens
+            .iter()
+            .map(|a| {
+                let a = Elm::from_wreath(&a.w);
+                gens.iter()
+                    .map(|b| {
+                        let b = Elm::from_wreath(&b.w);
+                        a.mul(&b) == b.mul(&a)
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            metric,
+            moves: res,
+            commute,
+            heuristics: vec![],
+        }
+    }
+
+    pub fn with_heuristic(mut self, h: impl Heuristic + 'static) -> Self {
+        self.heuristics.push(Box::new(h));
+        self
+    }
+
+    /// The moves searched, for building pattern databases.
+    pub fn moves(&self) -> Vec<Operation> {
+        self.moves.iter().map(|m| m.ope.clone()).collect()
+    }
+
+    fn estimate(&self, s: &Elm) -> usize {
+        self.heuristics
+            .iter()
+            .map(|h| h.estimate(&s.perm, &s.twist))
+            .max()
+            .unwrap_or(0)
+    }
+
+    // whether `next` may follow the moves of `path`; sequences which a
+    // shorter or an earlier ordered one reaches as well are left out
+    fn allowed(&self, path: &[usize], next: usize) -> bool {
+        let Some(&prev) = path.last() else {
+            return true;
+        };
+        let (m, p) = (&self.moves[next], &self.moves[prev]);
+        if m.axis == p.axis {
+            // in the quarter metric a quarter turn may be repeated as long
+            // as that is shorter than turning the other way
+            if self.metric == Metric::Face || m.power != p.power {
+                return false;
+            }
+            let run = path
+                .iter()
+                .rev()
+                .take_while(|&&i| self.moves[i].axis == m.axis)
+                .count();
+            let twice = 2 * (run + 1);
+            return twice < m.order || (twice == m.order && m.power == 1);
+        }
+        !(self.commute[m.axis][p.axis] && m.axis < p.axis)
+    }
+
+    // depth first below `g` moves made, false once the search must end
+    fn dfs(&self, run: &mut Run, g: usize, bound: usize) -> bool {
+        run.nodes += 1;
+        if run.limits.max_nodes.is_some_and(|n| run.nodes > n) {
+            run.stop = Some(Outcome::NodeLimit);
+            return false;
+        }
+        if run.nodes.is_multiple_of(1024)
+            && run.limits.max_time.is_some_and(|t| run.start.elapsed() > t)
+        {
+            run.stop = Some(Outcome::TimeLimit);
+            return false;
+        }
+
+        let f = g + self.estimate(&run.states[g]);
+        if f > bound {
+            run.next_bound = run.next_bound.min(f);
+            return true;
+        }
+        if g == bound && run.states[g].is_e() {
+            // the moves were made first to last, so they are written the
+            // other way round
+            let word = run
+                .path
+                .iter()
+                .rev()
+                .map(|&i| self.moves[i].ope.clone())
+                .collect::<Vec<_>>();
+            if !(run.on_solution)(&word) {
+                run.stop = Some(Outcome::Stopped);
+                return false;
+            }
+        }
+
+        for i in 0..self.moves.len() {
+            if !self.allowed(&run.path, i) {
+                continue;
+            }
+            let (done, rest) = run.states.split_at_mut(g + 1);
+            self.moves[i].elm.mul_into(&done[g], &mut rest[0]);
+            run.path.push(i);
+            let go_on = self.dfs(run, g + 1, bound);
+            run.path.pop();
+            if !go_on {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Reports the solutions of `state`, shortest first and each in written
+    /// order, to `on_solution` as they are found; it returns whether to go
+    /// on. The search never ends on an unsolvable state unless limited.
+    pub fn solve(
+        &self,
+        state: &WreathElm,
+        limits: Limits,
+        mut on_solution: impl FnMut(&[Operation]) -> bool,
+    ) -> Result<Report> {
+        let n = self.moves.first().map(|m| m.elm.perm.len()).unwrap_or(0);
+        if state.size() != n {
+            bail!("state has {} pieces, expected {}", state.size(), n);
+        }
+        let start = Elm::from_wreath(state);
+        let mut run = Run {
+            limits,
+            start: Instant::now(),
+            nodes: 0,
+            stop: None,
+            next_bound: 0,
+            states: vec![start.clone()],
+            path: vec![],
+            on_solution: &mut on_solution,
+        };
+        let mut bound = self.estimate(&start);
+        // no solution is shorter than the first estimate, which may already
+        // exceed the longest length allowed
+        let mut length = bound
+            .checked_sub(1)
+            .map(|l| limits.max_length.map_or(l, |m| m.min(l)));
+        while limits.max_length.is_none_or(|l| bound <= l) {
+            run.states.resize(bound + 2, start.clone());
+            run.next_bound = usize::MAX;
+            if !self.dfs(&mut run, 0, bound) {
+                break;
+            }
+            if run.next_bound == usize::MAX {
+                // no move sequence is left to try
+                length = limits.max_length.or(length);
+                break;
+            }
+            // no solution is shorter than the next bound but longer than
+            // the lengths searched so far
+            length = Some(
+                limits
+                    .max_length
+                    .map_or(run.next_bound - 1, |l| l.min(run.next_bound - 1)),
+            );
+            bound = run.next_bound;
+        }
+        let outcome = run.stop.unwrap_or(Outcome::Exhausted);
+        Ok(Report {
+            outcome,
+            nodes: run.nodes,
+            length,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::coordinate;
+    use crate::movetable::MoveTables;
+    use crate::pdb::PatternDatabase;
+    use crate::puzzle::Puzzle;
+    use crate::rng::XorShift;
+    use crate::rotation::{seven_corner_moves, seven_corner_state};
+    use crate::wreath::{decode, evaluate, identity_matrix};
+
+    fn searcher(moves: &MoveSet, metric: Metric) -> Search {
+        let search = Search::new(moves, metric);
+        let opes = search.moves();
+        search
+            .with_heuristic(PermutationDatabase::largest(&opes, 1 << 16).unwrap())
+            .with_heuristic(OrientationDatabase::new(&opes).unwrap())
+    }
+
+    fn state_of(moves: &MoveSet, alg: &str) -> WreathElm {
+        let word = moves.parse_word(alg).unwrap();
+        decode(&evaluate(&word, &identity_matrix(moves.size())))
+    }
+
+    // the first `count` solutions, each checked to solve `state`
+    fn solutions(search: &Search, state: &WreathElm, count: usize) -> (Vec<usize>, Report) {
+        let mut lengths = vec![];
+        let report = search
+            .solve(state, Limits::default(), |word| {
+                assert!(decode(&evaluate(word, &state.get_matrix())).is_e());
+                lengths.push(word.len());
+                lengths.len() < count
+            })
+            .unwrap();
+        (lengths, report)
+    }
+
+    // index of the move named `name` in `search.moves()`
+    fn index(search: &Search, name: &str) -> usize {
+        search.moves().iter().position(|o| o.name == name).unwrap()
+    }
+
+    #[test]
+    fn lengths_agree_with_the_pattern_database() {
+        let tables = MoveTables::cube();
+        let db = PatternDatabase::generate(&tables).unwrap();
+        let cube = MoveSet::cube();
+        let seven = seven_corner_moves();
+        let search = searcher(&seven, Metric::Quarter);
+        let perms = PermutationDatabase::largest(&search.moves(), 1 << 16).unwrap();
+        let twists = OrientationDatabase::new(&search.moves()).unwrap();
+
+        let mut rng = XorShift::new(17);
+        let gens = cube.generators();
+        for i in 0..8 {
+            let word = (0..i + 2)
+                .map(|_| gens[rng.below(gens.len())].clone())
+                .collect::<Vec<_>>();
+            let state = decode(&evaluate(&word, &identity_matrix(8)));
+            let distance = db.distance(&tables, coordinate(&state).unwrap()).unwrap();
+            let state = seven_corner_state(&state).unwrap();
+
+            // both databases are admissible
+            let elm = Elm::from_wreath(&state);
+            assert!(perms.estimate(&elm.perm, &elm.twist) <= distance);
+            assert!(twists.estimate(&elm.perm, &elm.twist) <= distance);
+
+            let (lengths, report) = solutions(&search, &state, 1);
+            assert_eq!(lengths, vec![distance], "{}", state);
+            assert_eq!(report.outcome, Outcome::Stopped);
+            assert_eq!(report.length, distance.checked_sub(1));
+        }
+    }
+
+    #[test]
+    fn pyraminx_tips_and_skewb() {
+        let tips = Puzzle::bundled("pyraminx-tips").unwrap();
+        let search = searcher(tips.moves(), Metric::Quarter);
+        // each twisted tip takes one move
+        let state = state_of(tips.moves(), "u l' b");
+        let (lengths, _) = solutions(&search, &state, 1);
+        assert_eq!(lengths, vec![3]);
+
+        let skewb = Puzzle::bundled("skewb-corners").unwrap();
+        let search = searcher(skewb.moves(), Metric::Quarter);
+        let state = state_of(skewb.moves(), "R U' L B R'");
+        let (lengths, report) = solutions(&search, &state, 20);
+        assert_eq!(lengths.len(), 20);
+        assert_eq!(report.outcome, Outcome::Stopped);
+        assert!(lengths.windows(2).all(|w| w[0] <= w[1]));
+        assert!(lengths[0] <= 5);
+    }
+
+    #[test]
+    fn node_limit_and_stop() {
+        let seven = seven_corner_moves();
+        let search = searcher(&seven, Metric::Quarter);
+        let state = state_of(&seven, "R U R' U' F R U' F'");
+
+        let limits = Limits {
+            max_nodes: Some(10),
+            ..Limits::default()
+        };
+        let report = search.solve(&state, limits, |_| true).unwrap();
+        assert_eq!(report.outcome, Outcome::NodeLimit);
+        assert_eq!(report.nodes, 11);
+
+        let mut found = 0;
+        let report = search
+            .solve(&state, Limits::default(), |_| {
+                found += 1;
+                false
+            })
+            .unwrap();
+        assert_eq!(report.outcome, Outcome::Stopped);
+        assert_eq!(found, 1);
+    }
+
+    #[test]
+    fn max_length_is_reported() {
+        let seven = seven_corner_moves();
+        let search = searcher(&seven, Metric::Quarter);
+        let state = state_of(&seven, "R U");
+        let limits = Limits {
+            max_length: Some(4),
+            ..Limits::default()
+        };
+        let mut lengths = vec![];
+        let report = search
+            .solve(&state, limits, |word| {
+                lengths.push(word.len());
+                true
+            })
+            .unwrap();
+        assert_eq!(report.outcome, Outcome::Exhausted);
+        assert_eq!(report.length, Some(4));
+        assert_eq!(lengths, vec![2]);
+
+        // the first estimate is already too long
+        let state = state_of(&seven, "R U F R' U2");
+        let limits = Limits {
+            max_length: Some(1),
+            ..Limits::default()
+        };
+        let report = search.solve(&state, limits, |_| true).unwrap();
+        assert_eq!(report.outcome, Outcome::Exhausted);
+        assert_eq!(report.length, Some(1));
+        assert_eq!(report.nodes, 0);
+    }
+
+    #[test]
+    fn face_metric() {
+        let seven = seven_corner_moves();
+        let search = searcher(&seven, Metric::Face);
+        assert_eq!(search.moves().len(), 9);
+        let state = state_of(&seven, "R2 U2 F2");
+        let (lengths, _) = solutions(&search, &state, 1);
+        assert_eq!(lengths, vec![3]);
+
+        let quarter = searcher(&seven, Metric::Quarter);
+        assert_eq!(quarter.moves().len(), 6);
+        let (lengths, _) = solutions(&quarter, &state, 1);
+        assert_eq!(lengths, vec![6]);
+    }
+
+    #[test]
+    fn repeated_and_commuting_moves() {
+        let cube = MoveSet::cube();
+        let search = Search::new(&cube, Metric::Quarter);
+        let (r, ri) = (index(&search, "R"), index(&search, "R^{-1}"));
+        let (u, d) = (index(&search, "U"), index(&search, "D"));
+        // R R is the half turn, R R R is shorter as R', R' R' repeats R R
+        assert!(search.allowed(&[r], r));
+        assert!(!search.allowed(&[r, r], r));
+        assert!(!search.allowed(&[ri], ri));
+        assert!(!search.allowed(&[r], ri));
+        // U and D commute, so only one order of them is searched
+        assert_ne!(search.allowed(&[u], d), search.allowed(&[d], u));
+        assert!(search.allowed(&[u], r) && search.allowed(&[r], u));
+
+        let face = Search::new(&cube, Metric::Face);
+        let r = index(&face, "R");
+        assert!(!face.allowed(&[r], index(&face, "R2")));
+        assert!(!face.allowed(&[r], r));
+    }
+
+    #[test]
+    fn partial_ranks_are_dense() {
+        let n = 5;
+        let mut seen = [false; 60];
+        for a in 1..=n {
+            for b in (1..=n).filter(|&b| b != a) {
+                for c in (1..=n).filter(|&c| c != a && c != b) {
+                    let r = partial_rank(&[a, b, c], n);
+                    assert!(!seen[r]);
+                    seen[r] = true;
+                }
+            }
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+}
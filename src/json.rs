@@ -0,0 +1,224 @@
+use crate::cube::MoveSet;
+use crate::notation::format_word;
+use crate::solver::Algorithm;
+use crate::wreath::{Operation, WreathElm};
+use crate::{Mod3, Replacement};
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Map, Value};
+
+// JSON exchange format, see also `schema/rubikcube.schema.json`.
+//
+//   replacement  {"cycles": [[1, 6, 8, 7], [2, 4, 5]]}
+//                {"images": [6, 4, 3, 5, 2, 8, 1, 7]}
+//                Points start from 1. Cycles are disjoint when encoded; when
+//                decoded they are multiplied like in `Replacement::new`.
+//                `images[i - 1]` is where `i` goes and lists every point of
+//                `1..=n` once.
+//   vector       [0, 2, 0, 2, 1, 2, 0, 2]
+//                Twists at positions 1..=n, each 0, 1 or 2.
+//   wreath       {"replacement": <replacement>, "vector": <vector>}
+//                The vector fixes the number of pieces. With images their
+//                count must match it, cycles may not move a larger point.
+//   operation    {"name": "R", "element": <wreath>}
+//   algorithm    {"name": "(1 2 3)", "label": "[RF]U[RF]U^{-1}",
+//                 "word": "R' F' R F U R' F' R F U'", "element": <wreath>}
+//                The word is parsed with a move set; the element is
+//                optional when decoding and must agree with the word.
+
+/// How `encode_replacement` writes a permutation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplacementForm {
+    Cycles,
+    Images,
+}
+
+fn object<'a>(v: &'a Value, what: &str) -> Result<&'a Map<String, Value>> {
+    v.as_object()
+        .ok_or_else(|| anyhow!("{} must be an object, got {}", what, v))
+}
+
+fn field<'a>(obj: &'a Map<String, Value>, key: &str) -> Result<&'a Value> {
+    obj.get(key)
+        .ok_or_else(|| anyhow!("missing field `{}`", key))
+}
+
+fn string<'a>(obj: &'a Map<String, Value>, key: &str) -> Result<&'a str> {
+    field(obj, key)?
+        .as_str()
+        .ok_or_else(|| anyhow!("`{}` must be a string", key))
+}
+
+fn numbers(v: &Value, what: &str) -> Result<Vec<usize>> {
+    v.as_array()
+        .ok_or_else(|| anyhow!("{} must be an array, got {}", what, v))?
+        .iter()
+        .map(|x| {
+            x.as_u64()
+                .map(|x| x as usize)
+                .ok_or_else(|| anyhow!("{} must hold non-negative integers, got {}", what, x))
+        })
+        .collect()
+}
+
+pub fn encode_replacement(r: &Replacement, n: usize, form: ReplacementForm) -> Value {
+    match form {
+        ReplacementForm::Cycles => json!({ "cycles": r.cycles() }),
+        ReplacementForm::Images => json!({ "images": r.images(n) }),
+    }
+}
+
+/// Decodes a permutation of the points `1..=n`.
+pub fn decode_replacement(v: &Value, n: usize) -> Result<Replacement> {
+    let obj = object(v, "replacement")?;
+    match (obj.get("cycles"), obj.get("images")) {
+        (Some(cycles), None) => {
+            let table = cycles
+                .as_array()
+                .ok_or_else(|| anyhow!("`cycles` must be an array"))?
+                .iter()
+                .map(|c| numbers(c, "a cycle"))
+                .collect::<Result<Vec<_>>>()?;
+            Replacement::from_cycles(table, n)
+        }
+        (None, Some(images)) => {
+            let images = numbers(images, "`images`")?;
+            if images.len() != n {
+                bail!("{} images but {} points", images.len(), n);
+            }
+            Replacement::from_images(&images)
+        }
+        _ => bail!("replacement needs exactly one of `cycles` and `images`"),
+    }
+}
+
+pub fn encode_vector(vector: &[Mod3]) -> Value {
+    json!(vector.iter().map(|&m| usize::from(m)).collect::<Vec<_>>())
+}
+
+pub fn decode_vector(v: &Value) -> Result<Vec<Mod3>> {
+    numbers(v, "vector")?
+        .into_iter()
+        .map(|x| match x {
+            0..=2 => Ok(Mod3::from(x)),
+            _ => Err(anyhow!("twist {} is not one of 0, 1, 2", x)),
+        })
+        .collect()
+}
+
+pub fn encode_wreath(w: &WreathElm, form: ReplacementForm) -> Value {
+    json!({
+        "replacement": encode_replacement(w.get_replacement(), w.size(), form),
+        "vector": encode_vector(w.get_vector()),
+    })
+}
+
+pub fn decode_wreath(v: &Value) -> Result<WreathElm> {
+    let obj = object(v, "wreath element")?;
+    let replacement = field(obj, "replacement")?;
+    let vector = decode_vector(field(obj, "vector")?)?;
+    WreathElm::try_new(decode_replacement(replacement, vector.len())?, vector)
+}
+
+pub fn encode_operation(o: &Operation, form: ReplacementForm) -> Value {
+    json!({ "name": o.name, "element": encode_wreath(&o.w, form) })
+}
+
+pub fn decode_operation(v: &Value) -> Result<Operation> {
+    let obj = object(v, "operation")?;
+    let name = string(obj, "name")?;
+    if name.is_empty() {
+        bail!("operation name must not be empty");
+    }
+    Ok(Operation::new(name, decode_wreath(field(obj, "element")?)?))
+}
+
+pub fn encode_algorithm(a: &Algorithm, form: ReplacementForm) -> Value {
+    json!({
+        "name": a.name,
+        "label": a.label,
+        "word": format_word(&a.word),
+        "element": encode_wreath(&a.effect(), form),
+    })
+}
+
+/// Decodes an algorithm whose word is written with the moves of `moves`.
+pub fn decode_algorithm(v: &Value, moves: &MoveSet) -> Result<Algorithm> {
+    let obj = object(v, "algorithm")?;
+    let name = string(obj, "name")?;
+    let label = match obj.get("label") {
+        Some(_) => string(obj, "label")?,
+        None => name,
+    };
+    let word = moves.parse_word(string(obj, "word")?)?;
+    let alg = Algorithm::new(name, label, word);
+
+    if let Some(element) = obj.get("element") {
+        let expected = decode_wreath(element)?;
+        let actual = if alg.word.is_empty() {
+            WreathElm::e(moves.size())
+        } else {
+            alg.effect()
+        };
+        if expected != actual {
+            bail!(
+                "element {} does not match the word `{}`, which gives {}",
+                expected,
+                format_word(&alg.word),
+                actual
+            );
+        }
+    }
+    Ok(alg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::alter_book;
+
+    #[test]
+    fn wreath_round_trip() {
+        let w = "(1 6 8 7)(2 4 5) [0 2 0 2 1 2 0 2]"
+            .parse::<WreathElm>()
+            .unwrap();
+        for form in [ReplacementForm::Cycles, ReplacementForm::Images] {
+            let v = encode_wreath(&w, form);
+            let text = serde_json::to_string(&v).unwrap();
+            let back = serde_json::from_str::<Value>(&text).unwrap();
+            assert_eq!(decode_wreath(&back).unwrap(), w);
+        }
+        assert_eq!(
+            encode_wreath(&w, ReplacementForm::Images),
+            json!({
+                "replacement": { "images": [6, 4, 3, 5, 2, 8, 1, 7] },
+                "vector": [0, 2, 0, 2, 1, 2, 0, 2],
+            })
+        );
+    }
+
+    #[test]
+    fn algorithm_round_trip() {
+        let moves = MoveSet::cube();
+        for a in alter_book(&moves).iter() {
+            let back = decode_algorithm(&encode_algorithm(a, ReplacementForm::Cycles), &moves);
+            let back = back.unwrap();
+            assert_eq!((&back.name, &back.label), (&a.name, &a.label));
+            assert_eq!(back.effect(), a.effect());
+        }
+    }
+
+    #[test]
+    fn invalid_wreaths_are_rejected() {
+        for v in [
+            json!({ "replacement": { "cycles": [[1, 9]] }, "vector": [0, 0, 0, 0, 0, 0, 0, 0] }),
+            json!({ "replacement": { "images": [2, 1] }, "vector": [0, 0, 0, 0, 0, 0, 0, 0] }),
+            json!({ "replacement": { "cycles": [] }, "vector": [0, 3] }),
+            json!({ "replacement": { "cycles": [], "images": [1] }, "vector": [0] }),
+            json!({ "vector": [0] }),
+            json!({ "replacement": { "cycles": [[1, 100000000000000u64]] }, "vector": [0] }),
+            json!({ "replacement": { "cycles": [[1, u64::MAX]] }, "vector": [0] }),
+        ] {
+            assert!(decode_wreath(&v).is_err(), "{}", v);
+        }
+    }
+}
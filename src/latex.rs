@@ -0,0 +1,255 @@
+use crate::wreath::{decode, Matrix};
+use crate::{Mod3, Replacement};
+use std::fmt::Write;
+
+// Names and labels already use TeX syntax (`U^{-1}`, `[RF]U[RF]U^{-1}`), so
+// they are typeset as they are, upright like the usual move notation. Only
+// an underscore before a word, as in `algebraic_solve`, is not a subscript.
+fn tex_label(label: &str) -> String {
+    let chars = label.chars().collect::<Vec<_>>();
+    let mut res = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        match c {
+            '#' | '%' | '&' => {
+                res.push('\\');
+                res.push(c);
+            }
+            '_' if chars[i + 1..]
+                .iter()
+                .take(2)
+                .filter(|c| c.is_alphabetic())
+                .count()
+                == 2 =>
+            {
+                res.push_str("\\_")
+            }
+            ' ' => res.push_str("\\ "),
+            _ => res.push(c),
+        }
+    }
+    format!("\\mathrm{{{}}}", res)
+}
+
+fn tex_text(s: &str) -> String {
+    let mut res = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\textbackslash{}"),
+            '~' => res.push_str("\\textasciitilde{}"),
+            '^' => res.push_str("\\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                res.push('\\');
+                res.push(c);
+            }
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+fn tex_entry(x: Option<Mod3>) -> &'static str {
+    match x {
+        None => "0",
+        Some(Mod3::Zero) => "1",
+        Some(Mod3::One) => "\\omega",
+        Some(Mod3::Two) => "\\omega^2",
+    }
+}
+
+/// A monomial matrix as `pmatrix` with entries 0, 1, ω and ω².
+pub fn tex_matrix(m: &Matrix) -> String {
+    let rows = m
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|&x| tex_entry(x))
+                .collect::<Vec<_>>()
+                .join(" & ")
+        })
+        .collect::<Vec<_>>();
+    format!(
+        "\\begin{{pmatrix}}\n{}\n\\end{{pmatrix}}",
+        rows.join(" \\\\\n")
+    )
+}
+
+pub fn tex_replacement(r: &Replacement) -> String {
+    if r.cycles().is_empty() {
+        return "e".to_string();
+    }
+    r.cycles()
+        .iter()
+        .map(|c| {
+            format!(
+                "({})",
+                c.iter()
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\\ ")
+            )
+        })
+        .collect()
+}
+
+pub fn tex_vector(v: &[Mod3]) -> String {
+    format!(
+        "({})",
+        v.iter()
+            .map(|m| usize::from(*m).to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    name: String,
+    matrix: Matrix,
+    labels: Vec<String>,
+}
+
+/// A standalone LaTeX document for a sequence of computations, each shown
+/// with its monomial matrix, the decoded replacement and vector and the
+/// labels of the algorithms used.
+#[derive(Debug, Clone)]
+pub struct Report {
+    title: String,
+    entries: Vec<Entry>,
+}
+
+impl Report {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            entries: vec![],
+        }
+    }
+
+    /// Adds a result, like `print_matrix(name, m)`.
+    pub fn add(&mut self, name: &str, m: &Matrix) -> &mut Self {
+        self.add_with_labels(name, m, &[])
+    }
+
+    /// Adds a result together with the labels of the algorithms which led to
+    /// it, e.g. `[RF]U[RF]U^{-1}`.
+    pub fn add_with_labels(&mut self, name: &str, m: &Matrix, labels: &[String]) -> &mut Self {
+        self.entries.push(Entry {
+            name: name.to_string(),
+            matrix: m.clone(),
+            labels: labels.to_vec(),
+        });
+        self
+    }
+
+    pub fn to_tex(&self) -> String {
+        let mut res = String::new();
+        writeln!(res, "\\documentclass{{article}}").unwrap();
+        writeln!(res, "\\usepackage{{amsmath}}").unwrap();
+        writeln!(res, "\\setcounter{{MaxMatrixCols}}{{24}}").unwrap();
+        writeln!(res).unwrap();
+        writeln!(res, "\\begin{{document}}").unwrap();
+        writeln!(res, "\\section*{{{}}}", tex_text(&self.title)).unwrap();
+
+        for entry in self.entries.iter() {
+            let w = decode(&entry.matrix);
+            writeln!(res).unwrap();
+            writeln!(res, "\\subsection*{{\\({}\\)}}", tex_label(&entry.name)).unwrap();
+            writeln!(res, "\\[").unwrap();
+            writeln!(
+                res,
+                "{} = {}",
+                tex_label(&entry.name),
+                tex_matrix(&entry.matrix)
+            )
+            .unwrap();
+            writeln!(res, "\\]").unwrap();
+            writeln!(
+                res,
+                "\\[ \\sigma = {}, \\quad v = {} \\]",
+                tex_replacement(w.get_replacement()),
+                tex_vector(w.get_vector())
+            )
+            .unwrap();
+            if !entry.labels.is_empty() {
+                writeln!(
+                    res,
+                    "Operations: {}",
+                    entry
+                        .labels
+                        .iter()
+                        .map(|l| format!("\\({}\\)", tex_label(l)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(res).unwrap();
+        writeln!(res, "\\end{{document}}").unwrap();
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::MoveSet;
+    use crate::wreath::{evaluate, identity_matrix};
+
+    fn balanced(tex: &str) -> bool {
+        let mut depth = 0i64;
+        let mut escaped = false;
+        for c in tex.chars() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '{' => depth += 1,
+                '}' if depth == 0 => return false,
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        depth == 0
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        assert_eq!(tex_label("U^{-1}"), "\\mathrm{U^{-1}}");
+        assert_eq!(tex_label("algebraic_solve"), "\\mathrm{algebraic\\_solve}");
+        assert_eq!(tex_label("a_1 #2"), "\\mathrm{a_1\\ \\#2}");
+        assert_eq!(tex_text("50% {x}_y"), "50\\% \\{x\\}\\_y");
+    }
+
+    #[test]
+    fn known_matrix() {
+        let m = vec![
+            vec![None, Some(Mod3::One), None],
+            vec![Some(Mod3::Zero), None, None],
+            vec![None, None, Some(Mod3::Two)],
+        ];
+        assert_eq!(
+            tex_matrix(&m),
+            "\\begin{pmatrix}\n0 & \\omega & 0 \\\\\n1 & 0 & 0 \\\\\n0 & 0 & \\omega^2\n\\end{pmatrix}"
+        );
+    }
+
+    #[test]
+    fn reports_are_balanced() {
+        let moves = MoveSet::cube();
+        let word = moves.parse_word("R U'").unwrap();
+        let m = evaluate(&word, &identity_matrix(moves.size()));
+        let tex = Report::new("R U' & {friends}_1")
+            .add_with_labels("algebraic_solve", &m, &["[RF]U[RF]U^{-1}".to_string()])
+            .to_tex();
+        assert!(balanced(&tex), "{}", tex);
+        assert_eq!(
+            tex.matches("\\begin{").count(),
+            tex.matches("\\end{").count()
+        );
+        assert_eq!(tex.matches("\\[").count(), tex.matches("\\]").count());
+        assert!(tex.contains("\\section*{R U' \\& \\{friends\\}\\_1}"));
+        assert!(tex.contains("\\mathrm{algebraic\\_solve} = \\begin{pmatrix}"));
+        assert!(tex.contains("Operations: \\(\\mathrm{[RF]U[RF]U^{-1}}\\)"));
+    }
+}
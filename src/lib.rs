@@ -1,9 +1,46 @@
+use anyhow::{anyhow, bail};
 use num_traits::{Num, One, Zero};
 use std::cmp::{Eq, PartialEq};
 use std::convert::From;
 use std::fmt;
 use std::fmt::Display;
 use std::ops;
+use std::str::FromStr;
+
+pub mod conjugacy;
+pub mod convention;
+pub mod coord;
+pub mod cube;
+pub mod diff;
+pub mod facelet;
+pub mod gap;
+pub mod group;
+pub mod ida;
+pub mod json;
+pub mod latex;
+pub mod linalg;
+pub mod movetable;
+pub mod notation;
+pub mod pdb;
+pub mod polya;
+pub mod puzzle;
+pub mod repl;
+pub mod rng;
+pub mod rotation;
+pub mod script;
+pub mod setup;
+pub mod solver;
+pub mod symmetry;
+pub mod trace;
+pub mod wreath;
+
+pub use convention::CubeConvention;
+pub use cube::MoveSet;
+pub use group::PuzzleGroup;
+pub use linalg::{Mod3Matrix, Mod3Vector};
+pub use puzzle::Puzzle;
+pub use solver::algebraic_solve;
+pub use wreath::{decode, evaluate, print_matrix, rotate, Matrix, Operation, WreathElm, WSIZE};
 
 #[derive(Debug, Clone)]
 pub struct Replacement {
@@ -23,12 +60,12 @@ impl Replacement {
 
     pub fn new(table: Vec<Vec<usize>>) -> Self {
         let original = table.clone();
-        if table.len() == 0 {
+        if table.is_empty() {
             return Self::e();
         }
 
         for v in table.iter() {
-            if v.len() == 0 {
+            if v.is_empty() {
                 panic!("empty vector");
             }
         }
@@ -56,12 +93,7 @@ impl Replacement {
     }
 
     pub fn get_correct_k(&self) -> Option<usize> {
-        for i in (1..=self.k).rev() {
-            if self.replace(i) != i {
-                return Some(i);
-            }
-        }
-        None
+        (1..=self.k).rev().find(|&i| self.replace(i) != i)
     }
 
     pub fn replace(&self, i: usize) -> usize {
@@ -92,12 +124,7 @@ impl Replacement {
             panic!("Dist 0 is not allowed");
         }
 
-        for i in 1..=self.k {
-            if self.replace(i) == val {
-                return Some(i);
-            }
-        }
-        None
+        (1..=self.k).find(|&i| self.replace(i) == val)
     }
 
     pub fn rearrange(&self) -> Self {
@@ -133,7 +160,75 @@ impl Replacement {
         Replacement::new(new_table)
     }
 
-    pub fn from_correspond_book(corr_book: &Vec<usize>) -> Self {
+    /// Disjoint cycles of length at least 2, each starting from its
+    /// smallest point, ordered by that point.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        self.rearrange().original
+    }
+
+    /// Cycle lengths in decreasing order, fixed points on `1..=n` included.
+    pub fn cycle_type(&self, n: usize) -> Vec<usize> {
+        let cycles = self.cycles();
+        let moved = cycles.iter().map(|c| c.len()).sum::<usize>();
+        let mut res = cycles.iter().map(|c| c.len()).collect::<Vec<_>>();
+        res.extend(std::iter::repeat_n(1, n.saturating_sub(moved)));
+        res.sort_unstable_by(|a, b| b.cmp(a));
+        res
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.cycles().iter().map(|c| c.len() - 1).sum::<usize>() % 2 == 1
+    }
+
+    /// Like `new`, but rejects point 0, points above `n` and points repeated
+    /// within a cycle instead of misbehaving. Empty cycles are dropped.
+    pub fn from_cycles(table: Vec<Vec<usize>>, n: usize) -> anyhow::Result<Self> {
+        let mut res = vec![];
+        for cycle in table {
+            for (i, &p) in cycle.iter().enumerate() {
+                if p == 0 {
+                    bail!("points start from 1");
+                }
+                if p > n {
+                    bail!("point {} is out of range 1..={}", p, n);
+                }
+                if cycle[..i].contains(&p) {
+                    bail!("point {} appears twice in a cycle", p);
+                }
+            }
+            if !cycle.is_empty() {
+                res.push(cycle);
+            }
+        }
+        Ok(Self::new(res))
+    }
+
+    /// The permutation sending `i` to `images[i - 1]`, which must list each
+    /// of `1..=images.len()` exactly once.
+    pub fn from_images(images: &[usize]) -> anyhow::Result<Self> {
+        let n = images.len();
+        let mut seen = vec![false; n + 1];
+        for &q in images.iter() {
+            if q == 0 || q > n {
+                bail!("image {} is out of range 1..={}", q, n);
+            }
+            if seen[q] {
+                bail!("image {} appears twice", q);
+            }
+            seen[q] = true;
+        }
+        if n == 0 {
+            return Ok(Self::e());
+        }
+        Ok(Self::from_correspond_book(images))
+    }
+
+    /// Images of `1..=n`, the inverse of `from_images`.
+    pub fn images(&self, n: usize) -> Vec<usize> {
+        (1..=n).map(|i| self.replace(i)).collect()
+    }
+
+    pub fn from_correspond_book(corr_book: &[usize]) -> Self {
         let &k = corr_book.iter().max().unwrap();
 
         let mut table = vec![];
@@ -161,6 +256,46 @@ impl Replacement {
         }
         Replacement::new(table)
     }
+
+    /// Position of the permutation of `1..=n` in lexicographic order of the
+    /// images, from its Lehmer code. Fails if it moves points beyond `n`.
+    pub fn rank(&self, n: usize) -> anyhow::Result<usize> {
+        if let Some(k) = self.get_correct_k().filter(|&k| k > n) {
+            bail!("{} moves point {}, beyond the {} points ranked", self, k, n);
+        }
+        let images = self.images(n);
+        let mut res = 0;
+        for (i, &q) in images.iter().enumerate() {
+            let smaller = images[i + 1..].iter().filter(|&&r| r < q).count();
+            res = res * (n - i) + smaller;
+        }
+        Ok(res)
+    }
+
+    /// The permutation of `1..=n` with the given `rank`.
+    pub fn unrank(rank: usize, n: usize) -> anyhow::Result<Self> {
+        let count = (1..=n).product::<usize>();
+        if rank >= count {
+            bail!(
+                "rank {} is beyond the {} permutations of {} points",
+                rank,
+                count,
+                n
+            );
+        }
+        let mut digits = vec![0; n];
+        let mut rest = rank;
+        for i in (0..n).rev() {
+            digits[i] = rest % (n - i);
+            rest /= n - i;
+        }
+        let mut unused = (1..=n).collect::<Vec<_>>();
+        let images = digits
+            .into_iter()
+            .map(|d| unused.remove(d))
+            .collect::<Vec<_>>();
+        Self::from_images(&images)
+    }
 }
 
 impl PartialEq for Replacement {
@@ -199,7 +334,55 @@ impl Display for Replacement {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Parses cycle notation such as `(1 6 8 7)(2 4 5)`; points may also be
+/// separated by commas as in `(1,2,3)`. `e` and `()` are the identity.
+/// Several cycles are multiplied like in `Replacement::new`.
+impl Replacement {
+    /// The largest point `FromStr` accepts, as it does not know the size.
+    pub const MAX_POINT: usize = 1 << 16;
+
+    /// Parses cycles such as `(1 2 3)(4 5)` or `e` on the points `1..=n`.
+    pub fn parse_within(s: &str, n: usize) -> anyhow::Result<Self> {
+        let s = s.trim();
+        if s == "e" {
+            return Ok(Self::e());
+        }
+
+        let mut table = vec![];
+        let mut rest = s;
+        while !rest.is_empty() {
+            let inner = rest
+                .strip_prefix('(')
+                .ok_or_else(|| anyhow!("expected `(` in `{}`", s))?;
+            let end = inner
+                .find(')')
+                .ok_or_else(|| anyhow!("missing `)` in `{}`", s))?;
+
+            let cycle = inner[..end]
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|t| !t.is_empty())
+                .map(|t| {
+                    t.parse::<usize>()
+                        .map_err(|_| anyhow!("`{}` is not a point", t))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            table.push(cycle);
+
+            rest = inner[end + 1..].trim_start();
+        }
+        Self::from_cycles(table, n)
+    }
+}
+
+impl FromStr for Replacement {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_within(s, Self::MAX_POINT)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Mod3 {
     Zero,
     One,
@@ -209,17 +392,17 @@ pub enum Mod3 {
 impl Mod3 {
     pub fn add_inv(&self) -> Self {
         match self {
-            &Mod3::Zero => Mod3::Zero,
-            &Mod3::One => Mod3::Two,
-            &Mod3::Two => Mod3::One,
+            Mod3::Zero => Mod3::Zero,
+            Mod3::One => Mod3::Two,
+            Mod3::Two => Mod3::One,
         }
     }
 
     pub fn mul_inv(&self) -> Self {
         match self {
-            &Mod3::Zero => panic!("0 is not allowed"),
-            &Mod3::One => Mod3::One,
-            &Mod3::Two => Mod3::Two,
+            Mod3::Zero => panic!("0 is not allowed"),
+            Mod3::One => Mod3::One,
+            Mod3::Two => Mod3::Two,
         }
     }
 }
@@ -342,7 +525,7 @@ impl ops::Sub for Mod3 {
     fn sub(self, other: Self) -> Self {
         match (self, other) {
             (Mod3::Zero, _) => other.add_inv(),
-            (_, Mod3::Zero) => self.add_inv(),
+            (_, Mod3::Zero) => self,
             (Mod3::One, Mod3::One) => Mod3::Zero,
             (Mod3::One, Mod3::Two) => Mod3::Two,
             (Mod3::Two, Mod3::One) => Mod3::One,
@@ -360,6 +543,7 @@ impl ops::SubAssign for Mod3 {
 impl ops::Div for Mod3 {
     type Output = Self;
 
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn div(self, other: Self) -> Self {
         if other.is_zero() {
             panic!("0 division occured.");
@@ -382,3 +566,101 @@ impl ops::Rem for Mod3 {
         todo!()
     }
 }
+
+/// Finds coefficients `c` such that `sum c[i] * basis[i] == target` over Z/3.
+/// Returns `None` when `target` is outside the span of `basis`.
+/// Free coefficients are fixed to zero.
+pub fn solve_combination(basis: &[Vec<Mod3>], target: &[Mod3]) -> Option<Vec<Mod3>> {
+    let columns = basis
+        .iter()
+        .map(|v| Mod3Vector::from(v.as_slice()))
+        .collect::<Vec<_>>();
+    let a = if columns.is_empty() {
+        Mod3Matrix::zeros(target.len(), 0)
+    } else {
+        Mod3Matrix::from_columns(&columns)
+    };
+
+    a.solve(&Mod3Vector::from(target)).map(|x| x.into_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Mod3; 3] = [Mod3::Zero, Mod3::One, Mod3::Two];
+
+    #[test]
+    fn subtraction_undoes_addition() {
+        for a in ALL {
+            for b in ALL {
+                assert_eq!(a + b - b, a, "{:?} + {:?} - {:?}", a, b, b);
+                assert_eq!(a - b, a + b.add_inv());
+            }
+        }
+        assert_eq!(Mod3::Two - Mod3::Zero, Mod3::Two);
+    }
+
+    fn vector(v: &[usize]) -> Vec<Mod3> {
+        v.iter().map(|&x| Mod3::from(x)).collect()
+    }
+
+    #[test]
+    fn solve_combination_inside_the_span() {
+        let basis = [vector(&[1, 2, 0]), vector(&[0, 1, 2])];
+        let target = vector(&[2, 0, 1]);
+        let c = solve_combination(&basis, &target).unwrap();
+        let sum = (0..3)
+            .map(|i| c[0] * basis[0][i] + c[1] * basis[1][i])
+            .collect::<Vec<_>>();
+        assert_eq!(sum, target);
+        assert_eq!(c, vector(&[2, 2]));
+    }
+
+    #[test]
+    fn solve_combination_outside_the_span() {
+        let basis = [vector(&[1, 2, 0]), vector(&[0, 1, 2])];
+        assert_eq!(solve_combination(&basis, &vector(&[1, 0, 0])), None);
+        assert_eq!(solve_combination(&[], &vector(&[0, 1])), None);
+        assert_eq!(solve_combination(&[], &vector(&[0, 0])), Some(vec![]));
+    }
+
+    #[test]
+    fn rank_and_unrank_are_inverse() {
+        for n in 0..=6 {
+            let count = (1..=n).product::<usize>();
+            for rank in 0..count {
+                let r = Replacement::unrank(rank, n).unwrap();
+                assert_eq!(r.rank(n).unwrap(), rank);
+            }
+            assert!(Replacement::unrank(count, n).is_err());
+        }
+    }
+
+    #[test]
+    fn rank_is_lexicographic() {
+        assert_eq!(Replacement::e().rank(7).unwrap(), 0);
+        let last = Replacement::from_images(&[7, 6, 5, 4, 3, 2, 1]).unwrap();
+        assert_eq!(last.rank(7).unwrap(), 5039);
+        let r = "(1 2)".parse::<Replacement>().unwrap();
+        assert_eq!(r.rank(3).unwrap(), 2);
+    }
+
+    #[test]
+    fn rank_rejects_points_beyond_n() {
+        let r = "(1 8)".parse::<Replacement>().unwrap();
+        assert!(r.rank(7).is_err());
+        assert!(r.rank(8).is_ok());
+    }
+
+    #[test]
+    fn parsed_points_are_bounded() {
+        assert!(Replacement::parse_within("(1 8)", 8).is_ok());
+        assert!(Replacement::parse_within("(1 9)", 8).is_err());
+        assert!("(1 100000000000000)".parse::<Replacement>().is_err());
+        assert!("(1 2)(0 3)".parse::<Replacement>().is_err());
+        assert!("(1 100000000000000) [0 0]"
+            .parse::<wreath::WreathElm>()
+            .is_err());
+    }
+}
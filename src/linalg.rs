@@ -0,0 +1,659 @@
+use crate::Mod3;
+use num_traits::Zero;
+use std::fmt;
+use std::ops;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mod3Vector {
+    data: Vec<Mod3>,
+}
+
+impl Mod3Vector {
+    pub fn new(data: Vec<Mod3>) -> Self {
+        Self { data }
+    }
+
+    pub fn zeros(n: usize) -> Self {
+        Self {
+            data: vec![Mod3::Zero; n],
+        }
+    }
+
+    /// The i-th standard basis vector of length n.
+    pub fn unit(n: usize, i: usize) -> Self {
+        let mut res = Self::zeros(n);
+        res.data[i] = Mod3::One;
+        res
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.data.iter().all(|m| m.is_zero())
+    }
+
+    pub fn as_slice(&self) -> &[Mod3] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<Mod3> {
+        self.data
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Mod3> {
+        self.data.iter()
+    }
+
+    pub fn dot(&self, other: &Self) -> Mod3 {
+        if self.len() != other.len() {
+            panic!("dimension mismatch");
+        }
+
+        self.data
+            .iter()
+            .zip(other.data.iter())
+            .fold(Mod3::Zero, |acc, (&a, &b)| acc + a * b)
+    }
+
+    /// Sum of all entries, i.e. the total twist of an orientation vector.
+    pub fn sum(&self) -> Mod3 {
+        self.data.iter().fold(Mod3::Zero, |acc, &m| acc + m)
+    }
+}
+
+impl From<Vec<Mod3>> for Mod3Vector {
+    fn from(data: Vec<Mod3>) -> Self {
+        Self::new(data)
+    }
+}
+
+impl From<&[Mod3]> for Mod3Vector {
+    fn from(data: &[Mod3]) -> Self {
+        Self::new(data.to_vec())
+    }
+}
+
+impl ops::Index<usize> for Mod3Vector {
+    type Output = Mod3;
+
+    fn index(&self, i: usize) -> &Mod3 {
+        &self.data[i]
+    }
+}
+
+impl ops::IndexMut<usize> for Mod3Vector {
+    fn index_mut(&mut self, i: usize) -> &mut Mod3 {
+        &mut self.data[i]
+    }
+}
+
+impl ops::Add for &Mod3Vector {
+    type Output = Mod3Vector;
+
+    fn add(self, other: Self) -> Mod3Vector {
+        if self.len() != other.len() {
+            panic!("dimension mismatch");
+        }
+
+        Mod3Vector::new(
+            self.data
+                .iter()
+                .zip(other.data.iter())
+                .map(|(&a, &b)| a + b)
+                .collect(),
+        )
+    }
+}
+
+impl ops::Add for Mod3Vector {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        &self + &other
+    }
+}
+
+impl ops::Sub for &Mod3Vector {
+    type Output = Mod3Vector;
+
+    fn sub(self, other: Self) -> Mod3Vector {
+        self + &(-other)
+    }
+}
+
+impl ops::Sub for Mod3Vector {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        &self - &other
+    }
+}
+
+impl ops::Neg for &Mod3Vector {
+    type Output = Mod3Vector;
+
+    fn neg(self) -> Mod3Vector {
+        Mod3Vector::new(self.data.iter().map(|m| m.add_inv()).collect())
+    }
+}
+
+impl ops::Neg for Mod3Vector {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        -&self
+    }
+}
+
+impl ops::Mul<Mod3> for &Mod3Vector {
+    type Output = Mod3Vector;
+
+    fn mul(self, c: Mod3) -> Mod3Vector {
+        Mod3Vector::new(self.data.iter().map(|&m| m * c).collect())
+    }
+}
+
+impl ops::Mul<Mod3> for Mod3Vector {
+    type Output = Self;
+
+    fn mul(self, c: Mod3) -> Self {
+        &self * c
+    }
+}
+
+impl fmt::Display for Mod3Vector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}]",
+            self.data
+                .iter()
+                .map(|&m| {
+                    let n: usize = m.into();
+                    n.to_string()
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mod3Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<Mod3>,
+}
+
+impl Mod3Matrix {
+    pub fn new(table: Vec<Vec<Mod3>>) -> Self {
+        let rows = table.len();
+        let cols = table.first().map(|v| v.len()).unwrap_or(0);
+        for v in table.iter() {
+            if v.len() != cols {
+                panic!("rows have different lengths");
+            }
+        }
+
+        Self {
+            rows,
+            cols,
+            data: table.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            data: vec![Mod3::Zero; rows * cols],
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut res = Self::zeros(n, n);
+        for i in 0..n {
+            res[(i, i)] = Mod3::One;
+        }
+        res
+    }
+
+    /// Builds the matrix whose j-th column is `columns[j]`.
+    pub fn from_columns(columns: &[Mod3Vector]) -> Self {
+        let rows = columns.first().map(|v| v.len()).unwrap_or(0);
+        let mut res = Self::zeros(rows, columns.len());
+        for (j, v) in columns.iter().enumerate() {
+            if v.len() != rows {
+                panic!("columns have different lengths");
+            }
+            for i in 0..rows {
+                res[(i, j)] = v[i];
+            }
+        }
+        res
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, i: usize) -> Mod3Vector {
+        Mod3Vector::from(&self.data[i * self.cols..(i + 1) * self.cols])
+    }
+
+    pub fn column(&self, j: usize) -> Mod3Vector {
+        Mod3Vector::new((0..self.rows).map(|i| self[(i, j)]).collect())
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut res = Self::zeros(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                res[(j, i)] = self[(i, j)];
+            }
+        }
+        res
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.rows == self.cols
+            && (0..self.rows).all(|i| {
+                (0..self.cols).all(|j| {
+                    let expected = if i == j { Mod3::One } else { Mod3::Zero };
+                    self[(i, j)] == expected
+                })
+            })
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        for j in 0..self.cols {
+            self.data.swap(a * self.cols + j, b * self.cols + j);
+        }
+    }
+
+    fn scale_row(&mut self, i: usize, c: Mod3) {
+        for j in 0..self.cols {
+            self[(i, j)] *= c;
+        }
+    }
+
+    // row[dst] -= c * row[src]
+    fn sub_row(&mut self, dst: usize, src: usize, c: Mod3) {
+        for j in 0..self.cols {
+            let v = self[(src, j)];
+            self[(dst, j)] -= c * v;
+        }
+    }
+
+    /// Reduced row echelon form together with the pivot columns.
+    pub fn rref(&self) -> (Self, Vec<usize>) {
+        let mut m = self.clone();
+        let mut pivots = vec![];
+        let mut r = 0;
+        for c in 0..m.cols {
+            if r == m.rows {
+                break;
+            }
+
+            let p = match (r..m.rows).find(|&i| !m[(i, c)].is_zero()) {
+                Some(p) => p,
+                None => continue,
+            };
+            m.swap_rows(r, p);
+            m.scale_row(r, m[(r, c)].mul_inv());
+
+            for i in 0..m.rows {
+                if i != r && !m[(i, c)].is_zero() {
+                    m.sub_row(i, r, m[(i, c)]);
+                }
+            }
+
+            pivots.push(c);
+            r += 1;
+        }
+        (m, pivots)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rref().1.len()
+    }
+
+    pub fn det(&self) -> Mod3 {
+        if self.rows != self.cols {
+            panic!("determinant of non-square matrix");
+        }
+
+        let mut m = self.clone();
+        let mut res = Mod3::One;
+        for c in 0..m.cols {
+            let p = match (c..m.rows).find(|&i| !m[(i, c)].is_zero()) {
+                Some(p) => p,
+                None => return Mod3::Zero,
+            };
+            if p != c {
+                m.swap_rows(c, p);
+                res = res.add_inv();
+            }
+
+            let pivot = m[(c, c)];
+            res *= pivot;
+            let inv = pivot.mul_inv();
+            for i in (c + 1)..m.rows {
+                if !m[(i, c)].is_zero() {
+                    m.sub_row(i, c, m[(i, c)] * inv);
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns `None` for singular matrices.
+    pub fn inverse(&self) -> Option<Self> {
+        if self.rows != self.cols {
+            panic!("inverse of non-square matrix");
+        }
+
+        let n = self.rows;
+        let mut aug = Self::zeros(n, 2 * n);
+        for i in 0..n {
+            for j in 0..n {
+                aug[(i, j)] = self[(i, j)];
+            }
+            aug[(i, n + i)] = Mod3::One;
+        }
+
+        let (reduced, pivots) = aug.rref();
+        if pivots.iter().copied().take(n).ne(0..n) {
+            return None;
+        }
+
+        let mut res = Self::zeros(n, n);
+        for i in 0..n {
+            for j in 0..n {
+                res[(i, j)] = reduced[(i, n + j)];
+            }
+        }
+        Some(res)
+    }
+
+    /// A basis of `{ x | self * x = 0 }`.
+    pub fn kernel(&self) -> Vec<Mod3Vector> {
+        let (reduced, pivots) = self.rref();
+        (0..self.cols)
+            .filter(|c| !pivots.contains(c))
+            .map(|free| {
+                let mut v = Mod3Vector::unit(self.cols, free);
+                for (i, &p) in pivots.iter().enumerate() {
+                    v[p] = reduced[(i, free)].add_inv();
+                }
+                v
+            })
+            .collect()
+    }
+
+    /// One solution `x` of `self * x = b`, with free variables set to zero.
+    /// Returns `None` when `b` is outside the column space.
+    pub fn solve(&self, b: &Mod3Vector) -> Option<Mod3Vector> {
+        if b.len() != self.rows {
+            panic!("dimension mismatch");
+        }
+
+        let mut aug = Self::zeros(self.rows, self.cols + 1);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                aug[(i, j)] = self[(i, j)];
+            }
+            aug[(i, self.cols)] = b[i];
+        }
+
+        let (reduced, pivots) = aug.rref();
+        if pivots.last() == Some(&self.cols) {
+            return None;
+        }
+
+        let mut res = Mod3Vector::zeros(self.cols);
+        for (i, &p) in pivots.iter().enumerate() {
+            res[p] = reduced[(i, self.cols)];
+        }
+        Some(res)
+    }
+}
+
+impl ops::Index<(usize, usize)> for Mod3Matrix {
+    type Output = Mod3;
+
+    fn index(&self, (i, j): (usize, usize)) -> &Mod3 {
+        if i >= self.rows || j >= self.cols {
+            panic!("index out of range");
+        }
+        &self.data[i * self.cols + j]
+    }
+}
+
+impl ops::IndexMut<(usize, usize)> for Mod3Matrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Mod3 {
+        if i >= self.rows || j >= self.cols {
+            panic!("index out of range");
+        }
+        &mut self.data[i * self.cols + j]
+    }
+}
+
+impl ops::Add for &Mod3Matrix {
+    type Output = Mod3Matrix;
+
+    fn add(self, other: Self) -> Mod3Matrix {
+        if self.rows != other.rows || self.cols != other.cols {
+            panic!("dimension mismatch");
+        }
+
+        Mod3Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: self
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .map(|(&a, &b)| a + b)
+                .collect(),
+        }
+    }
+}
+
+impl ops::Add for Mod3Matrix {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        &self + &other
+    }
+}
+
+impl ops::Sub for &Mod3Matrix {
+    type Output = Mod3Matrix;
+
+    fn sub(self, other: Self) -> Mod3Matrix {
+        self + &(-other)
+    }
+}
+
+impl ops::Sub for Mod3Matrix {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        &self - &other
+    }
+}
+
+impl ops::Neg for &Mod3Matrix {
+    type Output = Mod3Matrix;
+
+    fn neg(self) -> Mod3Matrix {
+        Mod3Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.data.iter().map(|m| m.add_inv()).collect(),
+        }
+    }
+}
+
+impl ops::Neg for Mod3Matrix {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        -&self
+    }
+}
+
+impl ops::Mul for &Mod3Matrix {
+    type Output = Mod3Matrix;
+
+    fn mul(self, other: Self) -> Mod3Matrix {
+        if self.cols != other.rows {
+            panic!("dimension mismatch");
+        }
+
+        let mut res = Mod3Matrix::zeros(self.rows, other.cols);
+        for i in 0..self.rows {
+            for j in 0..other.cols {
+                res[(i, j)] = self.row(i).dot(&other.column(j));
+            }
+        }
+        res
+    }
+}
+
+impl ops::Mul for Mod3Matrix {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        &self * &other
+    }
+}
+
+impl ops::Mul<&Mod3Vector> for &Mod3Matrix {
+    type Output = Mod3Vector;
+
+    fn mul(self, v: &Mod3Vector) -> Mod3Vector {
+        if self.cols != v.len() {
+            panic!("dimension mismatch");
+        }
+
+        Mod3Vector::new((0..self.rows).map(|i| self.row(i).dot(v)).collect())
+    }
+}
+
+impl ops::Mul<Mod3> for &Mod3Matrix {
+    type Output = Mod3Matrix;
+
+    fn mul(self, c: Mod3) -> Mod3Matrix {
+        Mod3Matrix {
+            rows: self.rows,
+            cols: self.cols,
+            data: self.data.iter().map(|&m| m * c).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Mod3Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for i in 0..self.rows {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{}",
+                (0..self.cols)
+                    .map(|j| {
+                        let n: usize = self[(i, j)].into();
+                        n.to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: &[&[usize]]) -> Mod3Matrix {
+        Mod3Matrix::new(
+            rows.iter()
+                .map(|r| r.iter().map(|&n| Mod3::from(n)).collect())
+                .collect(),
+        )
+    }
+
+    fn vector(v: &[usize]) -> Mod3Vector {
+        Mod3Vector::new(v.iter().map(|&n| Mod3::from(n)).collect())
+    }
+
+    #[test]
+    fn rref_of_a_rank_two_matrix() {
+        let a = matrix(&[&[1, 2, 0, 1], &[2, 1, 0, 2], &[0, 1, 1, 1]]);
+        let (reduced, pivots) = a.rref();
+        assert_eq!(
+            reduced,
+            matrix(&[&[1, 0, 1, 2], &[0, 1, 1, 1], &[0, 0, 0, 0]])
+        );
+        assert_eq!(pivots, vec![0, 1]);
+        assert_eq!(a.rank(), 2);
+    }
+
+    #[test]
+    fn kernel_is_annihilated() {
+        let a = matrix(&[&[1, 2, 0, 1], &[2, 1, 0, 2], &[0, 1, 1, 1]]);
+        let kernel = a.kernel();
+        assert_eq!(kernel, vec![vector(&[2, 2, 1, 0]), vector(&[1, 2, 0, 1])]);
+        for v in kernel.iter() {
+            assert!((&a * v).is_zero());
+        }
+    }
+
+    #[test]
+    fn inverse_of_an_invertible_matrix() {
+        let b = matrix(&[&[1, 1, 0], &[0, 1, 1], &[1, 0, 1]]);
+        let inv = b.inverse().unwrap();
+        assert_eq!(inv, matrix(&[&[2, 1, 2], &[2, 2, 1], &[1, 2, 2]]));
+        assert!((&b * &inv).is_identity());
+        assert_eq!(b.det(), Mod3::Two);
+    }
+
+    #[test]
+    fn singular_and_empty_inverses() {
+        let s = matrix(&[&[1, 2], &[2, 1]]);
+        assert_eq!(s.inverse(), None);
+        assert_eq!(s.det(), Mod3::Zero);
+        assert_eq!(
+            Mod3Matrix::zeros(0, 0).inverse(),
+            Some(Mod3Matrix::zeros(0, 0))
+        );
+    }
+
+    #[test]
+    fn solve_inside_and_outside_the_column_space() {
+        let a = matrix(&[&[1, 2, 0, 1], &[2, 1, 0, 2], &[0, 1, 1, 1]]);
+        let b = vector(&[1, 2, 2]);
+        let x = a.solve(&b).unwrap();
+        assert_eq!(&a * &x, b);
+        assert_eq!(x, vector(&[0, 2, 0, 0]));
+        assert_eq!(a.solve(&vector(&[1, 0, 0])), None);
+    }
+}
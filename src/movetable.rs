@@ -0,0 +1,282 @@
+use crate::coord::{twist_rank, twist_unrank, PERMUTATIONS, TWISTS};
+use crate::cube::MoveSet;
+use crate::rotation::{normalize, to_seven, REFERENCE};
+use crate::wreath::{Operation, WreathElm};
+use crate::{Mod3, Replacement};
+use anyhow::{anyhow, bail, Result};
+use std::io::{Read, Write};
+use std::path::Path;
+
+// A move acts on the permutation and on the twist coordinate separately:
+// the new twists only depend on the old twists and the move. So instead of
+// one table over all 3674160 states there are two small ones.
+//
+// File format, all integers little endian:
+//
+//   b"RCMT"  u32 version  u32 moves
+//   per move: u32 length, the name in UTF-8
+//   u16 entries of the permutation table, then of the twist table,
+//   row by row with one column per move
+
+const MAGIC: &[u8; 4] = b"RCMT";
+const VERSION: u32 = 1;
+// bounds against corrupt files
+const MAX_MOVES: usize = 256;
+const MAX_NAME: usize = 256;
+
+/// Where every move takes every permutation and every twist coordinate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveTables {
+    names: Vec<String>,
+    permutation: Vec<u16>,
+    twist: Vec<u16>,
+}
+
+// the move acting on the seven corners other than DBL
+fn seven_corner_move(o: &Operation) -> Result<WreathElm> {
+    match o.w.size() {
+        7 if o.w.get_vector().iter().fold(Mod3::Zero, |acc, &m| acc + m) != Mod3::Zero => {
+            bail!("move `{}` changes the total twist", o.name)
+        }
+        7 => Ok(o.w.clone()),
+        8 => to_seven(&normalize(&o.w, REFERENCE)?.0, REFERENCE),
+        n => bail!("move `{}` has {} pieces, not 7 or 8", o.name, n),
+    }
+}
+
+impl MoveTables {
+    /// Builds the tables for moves of the 2x2x2, on all eight corners or on
+    /// the seven of `rotation::seven_corner_moves`.
+    pub fn new(opes: &[Operation]) -> Result<Self> {
+        let moves = opes
+            .iter()
+            .map(seven_corner_move)
+            .collect::<Result<Vec<_>>>()?;
+        let k = moves.len();
+
+        let mut permutation = vec![0; PERMUTATIONS * k];
+        for p in 0..PERMUTATIONS {
+            let images = Replacement::unrank(p, 7)?.images(7);
+            for (m, w) in moves.iter().enumerate() {
+                let next = images
+                    .iter()
+                    .map(|&q| w.get_replacement().replace(q))
+                    .collect::<Vec<_>>();
+                permutation[p * k + m] = Replacement::from_images(&next)?.rank(7)? as u16;
+            }
+        }
+
+        let mut twist = vec![0; TWISTS * k];
+        for t in 0..TWISTS {
+            let vector = twist_unrank(t, 7)?;
+            for (m, w) in moves.iter().enumerate() {
+                // the twist at q moves on to where the move takes q
+                let mut next = w.get_vector().to_vec();
+                for (q, &v) in vector.iter().enumerate() {
+                    next[w.get_replacement().replace(q + 1) - 1] += v;
+                }
+                twist[t * k + m] = twist_rank(&next) as u16;
+            }
+        }
+
+        Ok(Self {
+            names: opes.iter().map(|o| o.name.clone()).collect(),
+            permutation,
+            twist,
+        })
+    }
+
+    /// Tables for the six face turns of the worksheet and their inverses.
+    pub fn cube() -> Self {
+        let moves = MoveSet::cube();
+        let mut opes = moves.generators().to_vec();
+        opes.extend(moves.generators().iter().map(|o| moves.inverse_of(o)));
+        Self::new(&opes).unwrap()
+    }
+
+    /// Names of the moves, in the order of the columns.
+    pub fn moves(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn apply_permutation(&self, p: usize, m: usize) -> usize {
+        self.permutation[p * self.names.len() + m] as usize
+    }
+
+    pub fn apply_twist(&self, t: usize, m: usize) -> usize {
+        self.twist[t * self.names.len() + m] as usize
+    }
+
+    /// A move undoing move `m` on every coordinate, if there is one.
+    pub fn inverse(&self, m: usize) -> Option<usize> {
+        (0..self.names.len()).find(|&i| {
+            (0..PERMUTATIONS).all(|p| self.apply_permutation(self.apply_permutation(p, m), i) == p)
+                && (0..TWISTS).all(|t| self.apply_twist(self.apply_twist(t, m), i) == t)
+        })
+    }
+
+    /// The coordinate reached from `coord` by move `m`.
+    pub fn apply(&self, coord: usize, m: usize) -> usize {
+        self.apply_permutation(coord / TWISTS, m) * TWISTS + self.apply_twist(coord % TWISTS, m)
+    }
+
+    pub fn write_to(&self, mut w: impl Write) -> Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&(self.names.len() as u32).to_le_bytes())?;
+        for name in self.names.iter() {
+            w.write_all(&(name.len() as u32).to_le_bytes())?;
+            w.write_all(name.as_bytes())?;
+        }
+        let entries = self
+            .permutation
+            .iter()
+            .chain(self.twist.iter())
+            .flat_map(|e| e.to_le_bytes())
+            .collect::<Vec<_>>();
+        w.write_all(&entries)?;
+        Ok(())
+    }
+
+    pub fn read_from(mut r: impl Read) -> Result<Self> {
+        fn u32_from(r: &mut impl Read) -> Result<u32> {
+            let mut buf = [0; 4];
+            r.read_exact(&mut buf)?;
+            Ok(u32::from_le_bytes(buf))
+        }
+
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("not a move table file");
+        }
+        let version = u32_from(&mut r)?;
+        if version != VERSION {
+            bail!("move tables of version {}, expected {}", version, VERSION);
+        }
+        let k = u32_from(&mut r)? as usize;
+        if k > MAX_MOVES {
+            bail!("{} moves, at most {} are supported", k, MAX_MOVES);
+        }
+        let mut names = vec![];
+        for _ in 0..k {
+            let len = u32_from(&mut r)? as usize;
+            if len > MAX_NAME {
+                bail!("move name of {} bytes", len);
+            }
+            let mut name = vec![0; len];
+            r.read_exact(&mut name)?;
+            names.push(String::from_utf8(name)?);
+        }
+
+        let mut read_table = |rows: usize, bound: usize| -> Result<Vec<u16>> {
+            let mut bytes = vec![0; rows * k * 2];
+            r.read_exact(&mut bytes)?;
+            let table = bytes
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .collect::<Vec<_>>();
+            if table.iter().any(|&e| e as usize >= bound) {
+                bail!("table entry out of range");
+            }
+            Ok(table)
+        };
+        let permutation = read_table(PERMUTATIONS, PERMUTATIONS)?;
+        let twist = read_table(TWISTS, TWISTS)?;
+        Ok(Self {
+            names,
+            permutation,
+            twist,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        // never overwrite, the file may be anything
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| anyhow!("cannot write {}: {}", path.display(), e))?;
+        let mut w = std::io::BufWriter::new(file);
+        self.write_to(&mut w)?;
+        w.flush()?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)
+            .map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+        Self::read_from(std::io::BufReader::new(file))
+            .map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::coordinate;
+    use crate::wreath::{decode, evaluate};
+
+    #[test]
+    fn apply_agrees_with_the_moves() {
+        let tables = MoveTables::cube();
+        let moves = MoveSet::cube();
+        // DBL stays solved, so that the moves need no turning of the cube
+        let word = moves.parse_word("R U' F R2 U F'").unwrap();
+        let state = decode(&evaluate(&word, &WreathElm::e(8).get_matrix()));
+        let c = coordinate(&state).unwrap();
+        for (m, name) in tables.moves().iter().enumerate() {
+            let o = moves.parse_word(name).unwrap();
+            let next = decode(&evaluate(&o, &state.get_matrix()));
+            assert_eq!(tables.apply(c, m), coordinate(&next).unwrap(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let tables = MoveTables::cube();
+        let mut bytes = vec![];
+        tables.write_to(&mut bytes).unwrap();
+        assert_eq!(MoveTables::read_from(bytes.as_slice()).unwrap(), tables);
+
+        let path = std::env::temp_dir().join(format!("rubikcube-{}.rcmt", std::process::id()));
+        tables.save(&path).unwrap();
+        let loaded = MoveTables::load(&path);
+        // an existing file is left as it is
+        let resaved = tables.save(&path);
+        std::fs::write(&path, b"not a table").unwrap();
+        let overwritten = tables.save(&path);
+        let kept = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), tables);
+        assert!(resaved.is_err());
+        assert!(overwritten.is_err());
+        assert_eq!(kept, b"not a table");
+    }
+
+    #[test]
+    fn twisting_moves_are_rejected() {
+        let twist = "e [1 0 0 0 0 0 0]".parse::<WreathElm>().unwrap();
+        assert!(MoveTables::new(&[Operation::new("T", twist)]).is_err());
+        let legal = "(1 2) [1 2 0 0 0 0 0]".parse::<WreathElm>().unwrap();
+        assert!(MoveTables::new(&[Operation::new("S", legal)]).is_ok());
+    }
+
+    #[test]
+    fn damaged_files_are_rejected() {
+        let mut bytes = vec![];
+        MoveTables::cube().write_to(&mut bytes).unwrap();
+
+        assert!(MoveTables::read_from(&bytes[..bytes.len() - 1]).is_err());
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(MoveTables::read_from(wrong_magic.as_slice()).is_err());
+        // the last twist entry beyond the 729 twists
+        let mut out_of_range = bytes.clone();
+        let n = out_of_range.len();
+        out_of_range[n - 2..].copy_from_slice(&(TWISTS as u16).to_le_bytes());
+        assert!(MoveTables::read_from(out_of_range.as_slice()).is_err());
+    }
+}
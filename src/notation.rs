@@ -0,0 +1,307 @@
+use crate::cube::MoveSet;
+use crate::wreath::{decode, evaluate, identity_matrix, Operation};
+use anyhow::{anyhow, bail, Result};
+
+// Grammar of algorithms, written the way the worksheet labels them:
+//
+//   word  := item*
+//   item  := atom power?
+//   atom  := MOVE | "(" word ")" | "[" word ("," | ":") word "]" | "[" atom atom "]"
+//   power := "'" | "^{-1}" | "^-1" | "^" INT | "^{" "-"? INT "}" | INT
+//
+// `[A, B]` (or `[AB]`) is the commutator A^{-1} B^{-1} A B and `[A: B]` the
+// conjugate A B A^{-1}. Moves are matched against the names of the move set,
+// longest name first.
+
+struct Parser<'a> {
+    moves: &'a MoveSet,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_ws(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.peek() {
+            Some(d) if d == c => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(d) => bail!("expected `{}` at {}, found `{}`", c, self.pos, d),
+            None => bail!("expected `{}` at end of input", c),
+        }
+    }
+
+    fn rest_starts_with(&self, s: &str) -> bool {
+        let rest = &self.chars[self.pos..];
+        let s = s.chars().collect::<Vec<_>>();
+        rest.len() >= s.len() && rest[..s.len()] == s[..]
+    }
+
+    fn int(&mut self) -> Result<i64> {
+        let start = self.pos;
+        if self.chars.get(self.pos) == Some(&'-') {
+            self.pos += 1;
+        }
+        while self.pos < self.chars.len() && self.chars[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+        let s = self.chars[start..self.pos].iter().collect::<String>();
+        s.parse::<i64>()
+            .map_err(|_| anyhow!("expected a number at {}", start))
+    }
+
+    fn word(&mut self) -> Result<Vec<Operation>> {
+        let mut res = vec![];
+        while let Some(c) = self.peek() {
+            if c == ')' || c == ']' || c == ',' || c == ':' {
+                break;
+            }
+            res.extend(self.item()?);
+        }
+        Ok(res)
+    }
+
+    fn item(&mut self) -> Result<Vec<Operation>> {
+        let atom = self.atom()?;
+        let power = self.power()?;
+        Ok(self.raise(&atom, power))
+    }
+
+    fn power(&mut self) -> Result<i64> {
+        // powers bind without whitespace: `U2`, `U'`, `U^{-1}`
+        match self.chars.get(self.pos) {
+            Some('\'') => {
+                self.pos += 1;
+                Ok(-1)
+            }
+            Some(c) if c.is_ascii_digit() => self.int(),
+            Some('^') => {
+                self.pos += 1;
+                if self.rest_starts_with("{") {
+                    self.pos += 1;
+                    let n = self.int()?;
+                    self.expect('}')?;
+                    Ok(n)
+                } else {
+                    self.int()
+                }
+            }
+            _ => Ok(1),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Vec<Operation>> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let w = self.word()?;
+                self.expect(')')?;
+                Ok(w)
+            }
+            Some('[') => {
+                self.pos += 1;
+                let items = self.items()?;
+                let a = items.iter().flatten().cloned().collect::<Vec<_>>();
+                let res = match self.peek() {
+                    Some(',') => {
+                        self.pos += 1;
+                        let b = self.word()?;
+                        self.commutator(&a, &b)
+                    }
+                    Some(':') => {
+                        self.pos += 1;
+                        let b = self.word()?;
+                        let mut res = a.clone();
+                        res.extend(b);
+                        res.extend(self.inverse(&a));
+                        res
+                    }
+                    _ => {
+                        // `[RF]`: exactly two items
+                        if items.len() != 2 {
+                            bail!("`[...]` without `,` needs exactly two items");
+                        }
+                        self.commutator(&items[0], &items[1])
+                    }
+                };
+                self.expect(']')?;
+                Ok(res)
+            }
+            Some(_) => self.name(),
+            None => bail!("unexpected end of input"),
+        }
+    }
+
+    // like `word`, but keeps the items apart for `[RF]`
+    fn items(&mut self) -> Result<Vec<Vec<Operation>>> {
+        let mut res = vec![];
+        while let Some(c) = self.peek() {
+            if c == ']' || c == ',' || c == ':' {
+                break;
+            }
+            res.push(self.item()?);
+        }
+        Ok(res)
+    }
+
+    fn name(&mut self) -> Result<Vec<Operation>> {
+        self.skip_ws();
+        let mut names = self
+            .moves
+            .generators()
+            .iter()
+            .map(|o| o.name.clone())
+            .collect::<Vec<_>>();
+        names.sort_by_key(|n| std::cmp::Reverse(n.chars().count()));
+
+        for name in names {
+            if self.rest_starts_with(&name) {
+                self.pos += name.chars().count();
+                return Ok(vec![self.moves.get(&name).unwrap().clone()]);
+            }
+        }
+        bail!(
+            "unknown move at {}: `{}`",
+            self.pos,
+            self.chars[self.pos..].iter().collect::<String>()
+        )
+    }
+
+    fn inverse(&self, w: &[Operation]) -> Vec<Operation> {
+        w.iter().rev().map(|o| self.moves.inverse_of(o)).collect()
+    }
+
+    fn commutator(&self, a: &[Operation], b: &[Operation]) -> Vec<Operation> {
+        let mut res = self.inverse(a);
+        res.extend(self.inverse(b));
+        res.extend_from_slice(a);
+        res.extend_from_slice(b);
+        res
+    }
+
+    // the power is reduced modulo the order of `w` once it reaches it, so
+    // that `(R U)999999999999` stays short
+    fn raise(&self, w: &[Operation], power: i64) -> Vec<Operation> {
+        let order = decode(&evaluate(w, &identity_matrix(self.moves.size()))).order() as i64;
+        let power = if power.abs() >= order {
+            power % order
+        } else {
+            power
+        };
+        let base = if power < 0 {
+            self.inverse(w)
+        } else {
+            w.to_vec()
+        };
+        let mut res = vec![];
+        for _ in 0..power.unsigned_abs() {
+            res.extend_from_slice(&base);
+        }
+        res
+    }
+}
+
+/// Parses an algorithm such as `R U R' U'`, `[RF]U[RF]U^{-1}` or
+/// `(BU^{-1})^3` into the operations of `moves`.
+pub fn parse_alg(moves: &MoveSet, s: &str) -> Result<Vec<Operation>> {
+    let mut parser = Parser {
+        moves,
+        chars: s.chars().collect(),
+        pos: 0,
+    };
+    let res = parser.word()?;
+    if let Some(c) = parser.peek() {
+        bail!("unexpected `{}` at {}", c, parser.pos);
+    }
+    Ok(res)
+}
+
+/// Writes a word with `'` for inverses, e.g. `R U' F`.
+pub fn format_word(word: &[Operation]) -> String {
+    word.iter()
+        .map(|o| match o.name.strip_suffix("^{-1}") {
+            Some(base) => format!("{}'", base),
+            None => o.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// the inverse move, named without a double `^{-1}`
+fn inverse_move(o: &Operation) -> Operation {
+    match o.name.strip_suffix("^{-1}") {
+        Some(base) => Operation::new(base, o.w.inverse()),
+        None => o.rev(),
+    }
+}
+
+/// `word` with adjacent inverses cancelled and runs of one move reduced
+/// modulo its order, e.g. `R U U' R` to `R R` and `R R R` to `R'`. It
+/// reaches the same state.
+pub fn reduce_word(word: &[Operation]) -> Vec<Operation> {
+    // runs of one move with their length modulo its order
+    let mut runs: Vec<(Operation, usize)> = vec![];
+    for o in word.iter() {
+        match runs.last_mut() {
+            Some((base, e)) if base.w == o.w => *e += 1,
+            Some((base, e)) if base.w.inverse() == o.w => *e += base.w.order() - 1,
+            _ => runs.push((o.clone(), 1)),
+        }
+        let (base, e) = runs.last_mut().unwrap();
+        *e %= base.w.order();
+        if *e == 0 {
+            runs.pop();
+        }
+    }
+    runs.into_iter()
+        .flat_map(|(base, e)| {
+            let order = base.w.order();
+            if 2 * e <= order {
+                vec![base; e]
+            } else {
+                vec![inverse_move(&base); order - e]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduce_word_cancels_and_shortens_runs() {
+        let moves = MoveSet::cube();
+        let reduce = |s: &str| format_word(&reduce_word(&moves.parse_word(s).unwrap()));
+        assert_eq!(reduce("R U U' R"), "R R");
+        assert_eq!(reduce("R R R"), "R'");
+        assert_eq!(reduce("R' R' R'"), "R");
+        assert_eq!(reduce("F R R R R F'"), "");
+        assert_eq!(reduce("R U R' U'"), "R U R' U'");
+    }
+
+    #[test]
+    fn large_powers_are_reduced_modulo_the_order() {
+        let moves = MoveSet::cube();
+        let parse = |s: &str| format_word(&moves.parse_word(s).unwrap());
+        assert_eq!(parse("R2"), "R R");
+        assert_eq!(parse("R^{-2}"), "R' R'");
+        assert_eq!(parse("R5"), "R");
+        assert_eq!(parse("R^{-5}"), "R'");
+        assert_eq!(parse("R4 U"), "U");
+        // R U has order 15 on the corners
+        assert_eq!(moves.parse_word("(R U)999999999999").unwrap().len(), 2 * 9);
+        assert!(moves.parse_word("R99999999999999999999").is_err());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::coord::{coordinate, STATES};
+use crate::movetable::MoveTables;
+use crate::wreath::{decode, evaluate, Operation, WreathElm};
+use anyhow::{anyhow, bail, Result};
+use std::io::Write;
+use std::path::Path;
+
+// The distance of every coordinate to the solved one, stored modulo 3 in two
+// bits, four states to a byte, 3 marking states not reached. Neighbours
+// differ in distance by at most one, so the value modulo 3 is enough to
+// step down to the solved state and thereby recover the exact distance.
+//
+// File format, all integers little endian:
+//
+//   b"RCPD"  u32 version  u32 states
+//   u32 moves, per move: u32 length, the name in UTF-8
+//   u32 depths, per depth: u32 number of states at that distance
+//   u64 FNV-1a checksum, then the table itself
+//
+// The checksum covers everything after the version but itself: the header
+// from the number of states on, then the table.
+
+const MAGIC: &[u8; 4] = b"RCPD";
+const VERSION: u32 = 2;
+const UNSEEN: u8 = 3;
+
+/// The complete distance table of the 2x2x2 up to whole-cube rotation for
+/// the moves of a `MoveTables`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternDatabase {
+    moves: Vec<String>,
+    depths: Vec<usize>,
+    table: Vec<u8>,
+}
+
+fn checksum(parts: &[&[u8]]) -> u64 {
+    parts
+        .iter()
+        .flat_map(|p| p.iter())
+        .fold(0xcbf2_9ce4_8422_2325, |h, &b| {
+            (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+impl PatternDatabase {
+    /// Explores every coordinate breadth first from the solved one. Stepping
+    /// down by the distance modulo 3 needs the moves to be closed under
+    /// inverses, as they are for `MoveTables::cube`.
+    pub fn generate(tables: &MoveTables) -> Result<Self> {
+        if let Some(m) = (0..tables.moves().len()).find(|&m| tables.inverse(m).is_none()) {
+            bail!(
+                "the inverse of `{}` is not among the moves",
+                tables.moves()[m]
+            );
+        }
+        let mut res = Self {
+            moves: tables.moves().to_vec(),
+            depths: vec![1],
+            table: vec![0xff; STATES.div_ceil(4)],
+        };
+        res.set(0, 0);
+        let mut layer = vec![0];
+        let mut depth = 0;
+        while !layer.is_empty() {
+            depth += 1;
+            let mut next_layer = vec![];
+            for &c in layer.iter() {
+                for m in 0..tables.moves().len() {
+                    let next = tables.apply(c, m);
+                    if res.get(next) == UNSEEN {
+                        res.set(next, (depth % 3) as u8);
+                        next_layer.push(next);
+                    }
+                }
+            }
+            if !next_layer.is_empty() {
+                res.depths.push(next_layer.len());
+            }
+            layer = next_layer;
+        }
+        Ok(res)
+    }
+
+    fn get(&self, c: usize) -> u8 {
+        (self.table[c / 4] >> (2 * (c % 4))) & 3
+    }
+
+    fn set(&mut self, c: usize, v: u8) {
+        let shift = 2 * (c % 4);
+        self.table[c / 4] = (self.table[c / 4] & !(3 << shift)) | (v << shift);
+    }
+
+    pub fn moves(&self) -> &[String] {
+        &self.moves
+    }
+
+    /// Number of states at each distance; the last distance is the
+    /// diameter, God's number for these moves.
+    pub fn depths(&self) -> &[usize] {
+        &self.depths
+    }
+
+    /// Moves, as indices into `tables.moves()`, of a shortest way from
+    /// `coord` to the solved coordinate, in the order they are made.
+    pub fn path(&self, tables: &MoveTables, coord: usize) -> Result<Vec<usize>> {
+        if tables.moves() != self.moves.as_slice() {
+            bail!("the move tables do not belong to this database");
+        }
+        if coord >= STATES || self.get(coord) == UNSEEN {
+            bail!("coordinate {} is not reachable", coord);
+        }
+        let mut res = vec![];
+        let mut cur = coord;
+        while cur != 0 {
+            let down = (self.get(cur) + 2) % 3;
+            let m = (0..self.moves.len())
+                .find(|&m| self.get(tables.apply(cur, m)) == down)
+                .ok_or_else(|| anyhow!("the database is inconsistent at {}", cur))?;
+            res.push(m);
+            cur = tables.apply(cur, m);
+        }
+        Ok(res)
+    }
+
+    /// The distance of `coord` from the solved coordinate.
+    pub fn distance(&self, tables: &MoveTables, coord: usize) -> Result<usize> {
+        Ok(self.path(tables, coord)?.len())
+    }
+
+    /// A shortest word in `opes`, face turns of the 2x2x2 which must include
+    /// those the database was made for, solving `state` up to whole-cube
+    /// rotation. The word is in written order like `Solution::operations`.
+    pub fn solve(&self, opes: &[Operation], state: &WreathElm) -> Result<Vec<Operation>> {
+        let value = |w: &WreathElm| -> Result<u8> {
+            match self.get(coordinate(w)?) {
+                UNSEEN => bail!("{} is not reachable", w),
+                v => Ok(v),
+            }
+        };
+        let mut res = vec![];
+        let mut cur = state.clone();
+        while coordinate(&cur)? != 0 {
+            let down = (value(&cur)? + 2) % 3;
+            let mut next = None;
+            for o in opes.iter() {
+                let w = decode(&evaluate(std::slice::from_ref(o), &cur.get_matrix()));
+                if value(&w)? == down {
+                    next = Some((o, w));
+                    break;
+                }
+            }
+            let (o, w) = next.ok_or_else(|| anyhow!("no move brings {} closer", cur))?;
+            res.push(o.clone());
+            cur = w;
+        }
+        res.reverse();
+        Ok(res)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        fn push(res: &mut Vec<u8>, n: usize) {
+            res.extend((n as u32).to_le_bytes());
+        }
+
+        let mut header = vec![];
+        push(&mut header, STATES);
+        push(&mut header, self.moves.len());
+        for name in self.moves.iter() {
+            push(&mut header, name.len());
+            header.extend(name.as_bytes());
+        }
+        push(&mut header, self.depths.len());
+        for &d in self.depths.iter() {
+            push(&mut header, d);
+        }
+
+        let mut res = MAGIC.to_vec();
+        push(&mut res, VERSION as usize);
+        res.extend(&header);
+        res.extend(checksum(&[&header, &self.table]).to_le_bytes());
+        res.extend(&self.table);
+        res
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        fn take<'a>(r: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+            if r.len() < n {
+                bail!("the file ends early");
+            }
+            let (head, tail) = r.split_at(n);
+            *r = tail;
+            Ok(head)
+        }
+        fn number(r: &mut &[u8]) -> Result<usize> {
+            Ok(u32::from_le_bytes(take(r, 4)?.try_into().unwrap()) as usize)
+        }
+
+        let mut r = bytes;
+        if take(&mut r, 4)? != MAGIC {
+            bail!("not a pattern database");
+        }
+        let version = number(&mut r)?;
+        if version != VERSION as usize {
+            bail!(
+                "pattern database of version {}, expected {}",
+                version,
+                VERSION
+            );
+        }
+        let start = r;
+        if number(&mut r)? != STATES {
+            bail!("pattern database for another number of states");
+        }
+        let mut moves = vec![];
+        for _ in 0..number(&mut r)? {
+            let len = number(&mut r)?;
+            moves.push(String::from_utf8(take(&mut r, len)?.to_vec())?);
+        }
+        let depths = (0..number(&mut r)?)
+            .map(|_| number(&mut r))
+            .collect::<Result<Vec<_>>>()?;
+        let header = &start[..start.len() - r.len()];
+        let expected = u64::from_le_bytes(take(&mut r, 8)?.try_into().unwrap());
+        let table = take(&mut r, STATES.div_ceil(4))?.to_vec();
+        if checksum(&[header, &table]) != expected {
+            bail!("checksum mismatch, the file is damaged");
+        }
+        if !r.is_empty() {
+            bail!("unexpected data after the table");
+        }
+        Ok(Self {
+            moves,
+            depths,
+            table,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        // never overwrite, the file may be anything
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(&self.to_bytes()))
+            .map_err(|e| anyhow!("cannot write {}: {}", path.display(), e))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes =
+            std::fs::read(path).map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+        Self::from_bytes(&bytes).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// Loads the database at `path`, or generates it and writes it there if
+    /// there is no such file. A file which is no valid database for the
+    /// moves of `tables` is an error and left alone.
+    pub fn load_or_generate(path: impl AsRef<Path>, tables: &MoveTables) -> Result<Self> {
+        let path = path.as_ref();
+        let exists = path
+            .try_exists()
+            .map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+        if !exists {
+            let db = Self::generate(tables)?;
+            db.save(path)?;
+            return Ok(db);
+        }
+        let db = Self::load(path)?;
+        if db.moves != tables.moves() {
+            bail!(
+                "{} is a database for the moves {}, not {}",
+                path.display(),
+                db.moves.join(" "),
+                tables.moves().join(" ")
+            );
+        }
+        Ok(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::MoveSet;
+    use std::sync::OnceLock;
+
+    fn cube() -> &'static (MoveTables, PatternDatabase) {
+        static CUBE: OnceLock<(MoveTables, PatternDatabase)> = OnceLock::new();
+        CUBE.get_or_init(|| {
+            let tables = MoveTables::cube();
+            let db = PatternDatabase::generate(&tables).unwrap();
+            (tables, db)
+        })
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("rubikcube-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn gods_number_in_quarter_turns() {
+        let (_, db) = cube();
+        assert_eq!(db.depths().iter().sum::<usize>(), STATES);
+        assert_eq!(db.depths().len() - 1, 14);
+        assert_eq!(&db.depths()[..4], &[1, 6, 27, 120]);
+        assert_eq!(db.depths()[14], 276);
+    }
+
+    #[test]
+    fn solutions_are_shortest() {
+        let (tables, db) = cube();
+        let moves = MoveSet::cube();
+        let mut opes = moves.generators().to_vec();
+        opes.extend(moves.generators().iter().map(|o| moves.inverse_of(o)));
+
+        let word = moves.parse_word("R U R' U' F2").unwrap();
+        let state = decode(&evaluate(&word, &WreathElm::e(8).get_matrix()));
+        let solution = db.solve(&opes, &state).unwrap();
+        assert_eq!(solution.len(), 6);
+        assert_eq!(db.distance(tables, coordinate(&state).unwrap()).unwrap(), 6);
+        let solved = decode(&evaluate(&solution, &state.get_matrix()));
+        assert_eq!(coordinate(&solved).unwrap(), 0);
+    }
+
+    #[test]
+    fn bytes_round_trip_and_damage() {
+        let (_, db) = cube();
+        let bytes = db.to_bytes();
+        assert_eq!(&PatternDatabase::from_bytes(&bytes).unwrap(), db);
+
+        let mut damaged = bytes.clone();
+        let n = damaged.len();
+        damaged[n - 1] ^= 1;
+        let err = PatternDatabase::from_bytes(&damaged).unwrap_err();
+        assert!(err.to_string().contains("checksum"), "{}", err);
+        assert!(PatternDatabase::from_bytes(&bytes[..n - 1]).is_err());
+
+        // the header is covered as well, here the number of depths
+        let at = 16 + db.moves.iter().map(|m| 4 + m.len()).sum::<usize>();
+        assert_eq!(&bytes[at..at + 4], &15u32.to_le_bytes());
+        let mut damaged = bytes.clone();
+        damaged[at] = 14;
+        let err = PatternDatabase::from_bytes(&damaged).unwrap_err();
+        assert!(err.to_string().contains("checksum"), "{}", err);
+        let mut renamed = bytes.clone();
+        renamed[20] = b'X';
+        assert!(PatternDatabase::from_bytes(&renamed).is_err());
+        assert!(PatternDatabase::from_bytes(b"fn main() {}").is_err());
+    }
+
+    #[test]
+    fn load_or_generate_leaves_other_files_alone() {
+        let (tables, db) = cube();
+
+        let path = temp_path("notes.txt");
+        std::fs::write(&path, "notes").unwrap();
+        let res = PatternDatabase::load_or_generate(&path, tables);
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(res.is_err());
+        assert_eq!(contents, b"notes");
+
+        let path = temp_path("table.rcpd");
+        let generated = PatternDatabase::load_or_generate(&path, tables);
+        let loaded = PatternDatabase::load_or_generate(&path, tables);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(&generated.unwrap(), db);
+        assert_eq!(&loaded.unwrap(), db);
+    }
+
+    #[test]
+    fn moves_must_be_closed_under_inverses() {
+        let moves = MoveSet::cube();
+        let tables = MoveTables::new(moves.generators()).unwrap();
+        assert!(PatternDatabase::generate(&tables).is_err());
+    }
+}
@@ -0,0 +1,449 @@
+use crate::facelet::{FaceletCube, FACELETS};
+use crate::symmetry::Symmetry;
+use crate::wreath::WreathElm;
+use crate::{Mod3, Replacement};
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt::{self, Display};
+
+// Burnside sums overflow u128 quickly, already 6^54 for the stickers of a
+// 3x3x3, so they are kept as naturals in base 2^32. Only what the counting
+// needs is implemented: sums, and products and quotients by machine numbers.
+
+/// A natural number of any size.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BigUint {
+    // little endian, without trailing zeros
+    digits: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    fn trim(&mut self) {
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let mut digits = vec![];
+        let mut carry = 0u64;
+        for i in 0..self.digits.len().max(other.digits.len()) {
+            let a = *self.digits.get(i).unwrap_or(&0) as u64;
+            let b = *other.digits.get(i).unwrap_or(&0) as u64;
+            let s = a + b + carry;
+            digits.push(s as u32);
+            carry = s >> 32;
+        }
+        digits.push(carry as u32);
+        let mut res = Self { digits };
+        res.trim();
+        res
+    }
+
+    pub fn mul_small(&self, m: u32) -> Self {
+        let mut digits = vec![];
+        let mut carry = 0u64;
+        for &d in self.digits.iter() {
+            let p = d as u64 * m as u64 + carry;
+            digits.push(p as u32);
+            carry = p >> 32;
+        }
+        digits.push(carry as u32);
+        let mut res = Self { digits };
+        res.trim();
+        res
+    }
+
+    pub fn pow_small(base: u32, exp: usize) -> Self {
+        (0..exp).fold(Self::from(1), |acc, _| acc.mul_small(base))
+    }
+
+    /// Quotient and remainder of the division by `d`, which must not be 0.
+    pub fn div_rem_small(&self, d: u32) -> (Self, u32) {
+        let mut digits = vec![0; self.digits.len()];
+        let mut rem = 0u64;
+        for i in (0..self.digits.len()).rev() {
+            let cur = (rem << 32) | self.digits[i] as u64;
+            digits[i] = (cur / d as u64) as u32;
+            rem = cur % d as u64;
+        }
+        let mut res = Self { digits };
+        res.trim();
+        (res, rem as u32)
+    }
+
+    /// The value if it fits into a `u128`.
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.digits.len() > 4 {
+            return None;
+        }
+        Some(
+            self.digits
+                .iter()
+                .rev()
+                .fold(0u128, |acc, &d| (acc << 32) | d as u128),
+        )
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        let mut res = Self {
+            digits: vec![n as u32, (n >> 32) as u32],
+        };
+        res.trim();
+        res
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // groups of nine decimal digits, least significant first
+        let mut groups = vec![];
+        let mut cur = self.clone();
+        while !cur.is_zero() {
+            let (q, r) = cur.div_rem_small(1_000_000_000);
+            groups.push(r);
+            cur = q;
+        }
+        write!(f, "{}", groups.pop().unwrap())?;
+        for g in groups.iter().rev() {
+            write!(f, "{:09}", g)?;
+        }
+        Ok(())
+    }
+}
+
+/// All products of `gens` acting on `1..=n`, the identity included.
+pub fn generate(gens: &[Replacement], n: usize) -> Vec<Replacement> {
+    let gens = gens.iter().map(|g| g.images(n)).collect::<Vec<_>>();
+    let start = (1..=n).collect::<Vec<_>>();
+    let mut seen = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([start]);
+    let mut res = vec![];
+    while let Some(p) = queue.pop_front() {
+        for g in gens.iter() {
+            let q = p.iter().map(|&x| g[x - 1]).collect::<Vec<_>>();
+            if seen.insert(q.clone()) {
+                queue.push_back(q);
+            }
+        }
+        res.push(Replacement::from_images(&p).unwrap());
+    }
+    res
+}
+
+/// The cycle index of a permutation group: the average over its elements of
+/// `x_1^{c_1} x_2^{c_2} ...`, where `c_k` counts the cycles of length `k`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleIndex {
+    degree: usize,
+    order: usize,
+    // exponents c_1, ..., c_n with the number of elements having them
+    terms: BTreeMap<Vec<usize>, usize>,
+}
+
+impl CycleIndex {
+    /// The cycle index of `group` acting on `1..=n`, which must list every
+    /// element of a group exactly once, e.g. as returned by `generate`.
+    pub fn new(group: &[Replacement], n: usize) -> Result<Self> {
+        let mut seen = HashSet::new();
+        let mut terms = BTreeMap::new();
+        for g in group.iter() {
+            if g.get_correct_k().is_some_and(|k| k > n) {
+                bail!("{} moves points beyond {}", g, n);
+            }
+            if !seen.insert(g.images(n)) {
+                bail!("{} is listed twice", g);
+            }
+            let mut exponents = vec![0; n];
+            for len in g.cycle_type(n) {
+                exponents[len - 1] += 1;
+            }
+            *terms.entry(exponents).or_insert(0) += 1;
+        }
+        if terms.is_empty() {
+            bail!("a group has at least the identity");
+        }
+        Ok(Self {
+            degree: n,
+            order: group.len(),
+            terms,
+        })
+    }
+
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// The monomials as exponents `c_1, ..., c_n` with their coefficients
+    /// before dividing by the order.
+    pub fn terms(&self) -> impl Iterator<Item = (&[usize], usize)> {
+        self.terms.iter().map(|(e, &c)| (e.as_slice(), c))
+    }
+
+    /// Evaluates the cycle index with `x_k` replaced by `value(k)`. The
+    /// result is exact, and fails if it is not a natural number.
+    pub fn substitute(&self, value: impl Fn(usize) -> u32) -> Result<BigUint> {
+        let mut sum = BigUint::default();
+        for (exponents, &count) in self.terms.iter() {
+            let mut term = BigUint::from(count as u64);
+            for (i, &c) in exponents.iter().enumerate() {
+                for _ in 0..c {
+                    term = term.mul_small(value(i + 1));
+                }
+            }
+            sum = sum.add(&term);
+        }
+        let order = match u32::try_from(self.order) {
+            Ok(order) => order,
+            Err(_) => bail!("the group order {} is too large", self.order),
+        };
+        let (res, rem) = sum.div_rem_small(order);
+        if rem != 0 {
+            bail!("the substitution gives a fraction with remainder {}", rem);
+        }
+        Ok(res)
+    }
+
+    /// Number of colorings of the points with `colors` colors up to the
+    /// group, by Burnside's lemma.
+    pub fn count_colorings(&self, colors: u32) -> BigUint {
+        self.substitute(|_| colors).unwrap()
+    }
+}
+
+impl Display for CycleIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms = self
+            .terms
+            .iter()
+            .rev()
+            .map(|(exponents, &count)| {
+                let mut monomial = exponents
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &c)| c > 0)
+                    .map(|(i, &c)| match c {
+                        1 => format!("x{}", i + 1),
+                        _ => format!("x{}^{}", i + 1, c),
+                    })
+                    .collect::<Vec<_>>();
+                if count > 1 {
+                    monomial.insert(0, count.to_string());
+                }
+                monomial.join(" ")
+            })
+            .collect::<Vec<_>>();
+        write!(f, "(1/{}) ({})", self.order, terms.join(" + "))
+    }
+}
+
+// Whole-cube rotations of the 2x2x2 as pairs of opposite face turns.
+const ROTATIONS: [(&str, &str); 3] = [("R", "L"), ("U", "D"), ("F", "B")];
+
+fn rotation_generators() -> Vec<FaceletCube> {
+    ROTATIONS
+        .iter()
+        .map(|&(face, opposite)| {
+            let mut cube = FaceletCube::solved();
+            cube.turn(face, 1).unwrap();
+            cube.turn(opposite, 3).unwrap();
+            cube
+        })
+        .collect()
+}
+
+/// The 24 rotations of the whole cube acting on the 24 facelets, numbered
+/// from 1 in the order of `facelet`.
+pub fn cube_rotations_on_facelets() -> Vec<Replacement> {
+    let gens = rotation_generators()
+        .iter()
+        .map(|cube| {
+            let mut images = vec![0; FACELETS];
+            for (i, &home) in cube.facelets().iter().enumerate() {
+                images[home] = i + 1;
+            }
+            Replacement::from_images(&images).unwrap()
+        })
+        .collect::<Vec<_>>();
+    generate(&gens, FACELETS)
+}
+
+/// The 24 rotations of the whole cube acting on the 8 corner positions.
+pub fn cube_rotations_on_corners() -> Vec<Replacement> {
+    let gens = rotation_generators()
+        .iter()
+        .map(|cube| cube.to_wreath().unwrap().get_replacement().clone())
+        .collect::<Vec<_>>();
+    generate(&gens, 8)
+}
+
+/// `syms` acting on the 24 pairs of a corner `p` and a twist `o`, numbered
+/// `3 (p - 1) + o + 1` like the wreath points of `gap`, by how they move a
+/// single twisted corner. Rotations keep the twist, the mirror negates it.
+pub fn symmetries_on_twists(syms: &[Symmetry]) -> Vec<Replacement> {
+    let n = 8;
+    syms.iter()
+        .map(|s| {
+            let mut images = vec![0; 3 * n];
+            for p in 1..=n {
+                let mut vector = vec![Mod3::Zero; n];
+                vector[p - 1] = Mod3::One;
+                let w = s.apply(&WreathElm::new(Replacement::e(), vector));
+                // the corner it goes to, from 0, and its twist, 1 or 2 under the mirror
+                let (q, &sign) = w
+                    .get_vector()
+                    .iter()
+                    .enumerate()
+                    .find(|(_, &m)| m != Mod3::Zero)
+                    .unwrap();
+                for o in 0..3 {
+                    images[3 * (p - 1) + o] = 3 * q + o * usize::from(sign) % 3 + 1;
+                }
+            }
+            Replacement::from_images(&images).unwrap()
+        })
+        .collect()
+}
+
+/// Number of twist vectors of `n` corners up to `group`, a group acting on
+/// the `3n` pairs of corner and twist as in `symmetries_on_twists`, listing
+/// each element once. With `legal` only those of total twist zero count.
+///
+/// By Burnside's lemma: a vector fixed by `g` is fixed along each cycle of
+/// corners, where it is given by a twist at the first corner which comes
+/// back to itself after one turn of the cycle.
+pub fn count_twist_patterns(group: &[Replacement], n: usize, legal: bool) -> Result<BigUint> {
+    let mut sum = BigUint::default();
+    for g in group.iter() {
+        if g.get_correct_k().is_some_and(|k| k > 3 * n) {
+            bail!("{} moves points beyond {}", g, 3 * n);
+        }
+        let images = g.images(3 * n);
+        let corner = |x: usize| (x - 1) / 3;
+        if (0..3 * n).any(|x| corner(images[x]) != corner(images[x - x % 3])) {
+            bail!("{} does not move twisted corners to twisted corners", g);
+        }
+
+        // fixed vectors by total twist modulo 3
+        let mut fixed = [BigUint::from(1), BigUint::default(), BigUint::default()];
+        let mut seen = vec![false; n];
+        for p in 0..n {
+            if seen[p] {
+                continue;
+            }
+            let mut on_cycle = [0; 3];
+            for o in 0..3 {
+                let start = 3 * p + o + 1;
+                let (mut x, mut total) = (start, 0);
+                loop {
+                    seen[corner(x)] = true;
+                    total += (x - 1) % 3;
+                    x = images[x - 1];
+                    if corner(x) == p {
+                        break;
+                    }
+                }
+                if x == start {
+                    on_cycle[total % 3] += 1;
+                }
+            }
+            let mut next = [BigUint::default(), BigUint::default(), BigUint::default()];
+            for (a, count) in fixed.iter().enumerate() {
+                for (b, &c) in on_cycle.iter().enumerate() {
+                    next[(a + b) % 3] = next[(a + b) % 3].add(&count.mul_small(c));
+                }
+            }
+            fixed = next;
+        }
+
+        if legal {
+            sum = sum.add(&fixed[0]);
+        } else {
+            sum = fixed.iter().fold(sum, |acc, f| acc.add(f));
+        }
+    }
+
+    let order = match u32::try_from(group.len()) {
+        Ok(0) => bail!("a group has at least the identity"),
+        Ok(order) => order,
+        Err(_) => bail!("the group order {} is too large", group.len()),
+    };
+    let (res, rem) = sum.div_rem_small(order);
+    if rem != 0 {
+        bail!("not a group, the orbits sum to a fraction");
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symmetry::{rotations, symmetries};
+
+    // orbits of the twist vectors under `syms`, by listing them
+    fn orbits(syms: &[Symmetry], legal: bool) -> usize {
+        let mut seen = HashSet::<Vec<usize>>::new();
+        let mut res = 0;
+        for code in 0..3usize.pow(8) {
+            let vector = (0..8)
+                .map(|i| Mod3::from(code / 3usize.pow(i) % 3))
+                .collect::<Vec<_>>();
+            let total = vector.iter().fold(Mod3::Zero, |acc, &m| acc + m);
+            if legal && total != Mod3::Zero {
+                continue;
+            }
+            let w = WreathElm::new(Replacement::e(), vector);
+            let key = |w: &WreathElm| w.get_vector().iter().map(|&m| usize::from(m)).collect();
+            if seen.insert(key(&w)) {
+                res += 1;
+                seen.extend(syms.iter().map(|s| key(&s.apply(&w))));
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn colorings_of_the_cube() {
+        let facelets = CycleIndex::new(&cube_rotations_on_facelets(), 24).unwrap();
+        assert_eq!(facelets.order(), 24);
+        assert_eq!(facelets.count_colorings(2).to_string(), "700688");
+        let corners = CycleIndex::new(&cube_rotations_on_corners(), 8).unwrap();
+        assert_eq!(corners.count_colorings(2).to_string(), "23");
+        assert_eq!(corners.count_colorings(3).to_string(), "333");
+    }
+
+    #[test]
+    fn twist_patterns_agree_with_orbits() {
+        for syms in [rotations(), symmetries()] {
+            let group = symmetries_on_twists(&syms);
+            for legal in [false, true] {
+                let count = count_twist_patterns(&group, 8, legal).unwrap();
+                assert_eq!(count.to_u128(), Some(orbits(&syms, legal) as u128));
+            }
+        }
+    }
+
+    #[test]
+    fn big_numbers() {
+        let n = BigUint::pow_small(6, 54);
+        assert_eq!(n.div_rem_small(6).0, BigUint::pow_small(6, 53));
+        assert_eq!(n.to_u128(), None);
+        assert_eq!(
+            BigUint::pow_small(10, 20).to_string(),
+            "100000000000000000000"
+        );
+    }
+}
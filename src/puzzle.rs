@@ -0,0 +1,227 @@
+use crate::cube::MoveSet;
+use crate::group::PuzzleGroup;
+use crate::solver::{algebraic_solve, Solution};
+use crate::wreath::{Operation, WreathElm};
+use anyhow::{anyhow, bail, Result};
+use std::path::Path;
+use std::str::FromStr;
+
+// Puzzle files list the pieces and the moves, one statement per line:
+//
+//   # comment
+//   name skewb-corners
+//   pieces 8
+//   modulus 3
+//   move R = (4 6 8) [0 0 0 1 2 1 0 1]
+//
+// Moves are written as cycles plus twist vector, as in `WreathElm::new`.
+const BUNDLED: [(&str, &str); 3] = [
+    ("2x2x2", include_str!("../puzzles/2x2x2.puzzle")),
+    (
+        "pyraminx-tips",
+        include_str!("../puzzles/pyraminx-tips.puzzle"),
+    ),
+    (
+        "skewb-corners",
+        include_str!("../puzzles/skewb-corners.puzzle"),
+    ),
+];
+
+/// A twisty puzzle given by its pieces and its generating moves.
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    name: String,
+    pieces: usize,
+    modulus: usize,
+    moves: MoveSet,
+}
+
+impl Default for Puzzle {
+    fn default() -> Self {
+        Self::bundled("2x2x2").unwrap()
+    }
+}
+
+fn is_move_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphabetic() || c == '_')
+}
+
+impl Puzzle {
+    pub fn bundled_names() -> Vec<&'static str> {
+        BUNDLED.iter().map(|(name, _)| *name).collect()
+    }
+
+    pub fn bundled(name: &str) -> Option<Self> {
+        BUNDLED
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, source)| source.parse().expect("bundled puzzle is valid"))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+        source
+            .parse()
+            .map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn pieces(&self) -> usize {
+        self.pieces
+    }
+
+    pub fn modulus(&self) -> usize {
+        self.modulus
+    }
+
+    pub fn moves(&self) -> &MoveSet {
+        &self.moves
+    }
+
+    pub fn group(&self) -> PuzzleGroup {
+        PuzzleGroup::new(&self.moves)
+    }
+
+    /// Whether the moves are those of the worksheet's 2x2x2, for which the
+    /// algebraic solver and the books of `solver` are written.
+    pub fn is_cube(&self) -> bool {
+        let cube = MoveSet::cube();
+        self.moves.generators().len() == cube.generators().len()
+            && self
+                .moves
+                .generators()
+                .iter()
+                .all(|o| cube.get(&o.name).is_some_and(|c| c.w == o.w))
+    }
+
+    /// Solves `state` with `algebraic_solve` on the 2x2x2 and through
+    /// `PuzzleGroup::solve` on any other puzzle.
+    pub fn solve(&self, state: &WreathElm) -> Result<Solution> {
+        if self.is_cube() {
+            algebraic_solve(state)
+        } else {
+            self.group().solve(state)
+        }
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut name = None;
+        let mut pieces = None;
+        let mut modulus = None;
+        let mut opes: Vec<Operation> = vec![];
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |msg: String| anyhow!("line {}: {}", i + 1, msg);
+            let (key, value) = match line.split_once(char::is_whitespace) {
+                Some((k, v)) => (k, v.trim()),
+                None => (line, ""),
+            };
+
+            match key {
+                "name" => name = Some(value.to_string()),
+                "pieces" | "modulus" => {
+                    let n = value
+                        .parse::<usize>()
+                        .map_err(|_| err(format!("`{}` needs a number", key)))?;
+                    if key == "pieces" {
+                        pieces = Some(n);
+                    } else if n != 3 {
+                        return Err(err(format!(
+                            "orientation modulus {} is not supported, only 3",
+                            n
+                        )));
+                    } else {
+                        modulus = Some(n);
+                    }
+                }
+                "move" => {
+                    let n =
+                        pieces.ok_or_else(|| err("`pieces` must come before the moves".into()))?;
+                    let (move_name, elem) = value.split_once('=').ok_or_else(|| {
+                        err("expected `move <name> = <cycles> [<twists>]`".into())
+                    })?;
+                    let move_name = move_name.trim();
+                    if !is_move_name(move_name) {
+                        return Err(err(format!("`{}` is not a valid move name", move_name)));
+                    }
+                    if opes.iter().any(|o| o.name == move_name) {
+                        return Err(err(format!("move `{}` is defined twice", move_name)));
+                    }
+                    let w = elem.parse::<WreathElm>().map_err(|e| err(e.to_string()))?;
+                    if w.size() != n {
+                        return Err(err(format!("expected {} twists, got {}", n, w.size())));
+                    }
+                    opes.push(Operation::new(move_name, w));
+                }
+                _ => return Err(err(format!("unknown statement `{}`", key))),
+            }
+        }
+
+        let pieces = pieces.ok_or_else(|| anyhow!("missing `pieces`"))?;
+        let modulus = modulus.ok_or_else(|| anyhow!("missing `modulus`"))?;
+        if opes.is_empty() {
+            bail!("a puzzle needs at least one move");
+        }
+
+        Ok(Self {
+            name: name.unwrap_or_else(|| "unnamed".to_string()),
+            pieces,
+            modulus,
+            moves: MoveSet::new(opes),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_group_orders() {
+        let orders = [
+            ("2x2x2", 88179840),
+            ("pyraminx-tips", 81),
+            ("skewb-corners", 8748),
+        ];
+        assert_eq!(Puzzle::bundled_names().len(), orders.len());
+        for (name, order) in orders {
+            let puzzle = Puzzle::bundled(name).unwrap();
+            assert_eq!(puzzle.name(), name);
+            assert_eq!(puzzle.group().order(), order, "{}", name);
+        }
+        assert!(Puzzle::default().is_cube());
+    }
+
+    fn error(source: &str) -> String {
+        source.parse::<Puzzle>().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(
+            error("pieces 2\nmodulus 3\ncolour red\n"),
+            "line 3: unknown statement `colour`"
+        );
+        assert_eq!(
+            error("pieces 2\nmodulus 3\nmove t = (1 2) [1 2 0]\n"),
+            "line 3: expected 2 twists, got 3"
+        );
+        assert_eq!(
+            error("pieces 2\nmodulus 2\n"),
+            "line 2: orientation modulus 2 is not supported, only 3"
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use crate::cube::MoveSet;
+use crate::diff::{diff, format_positions};
+use crate::facelet::FaceletCube;
+use crate::wreath::{
+    decode, evaluate, format_matrix, identity_matrix, Matrix, Operation, WreathElm,
+};
+use anyhow::{anyhow, bail, Result};
+use std::fmt::Write;
+
+pub const HELP: &str = "\
+  <name> = <expr>        define an element, e.g. `a = [R, F] U`
+  let <name> = <expr>    the same
+  gen <Name> = <elem>    define a generator, e.g. `gen X = (1 2)(3 4) [0 0 1 2 0 0 0 0]`
+  <expr>                 print as cycles and twist vector
+  matrix <expr>          print the monomial matrix
+  net <expr>             print the unfolded cube
+  order <expr>           order of the element
+  type <expr>            cycle type of the permutation
+  print [what] <expr>    print in a script, `what` is one of the above
+  assert_eq <a>, <b>     fail unless both sides agree
+  diff <a>, <b>          how <b> differs from <a>
+  list                   defined names
+  del <name>             remove a definition
+  undo                   revert the last definition or removal
+  help                   this text
+
+Expressions use the move notation (`R U' (FD)^5 [R: U]`) and may refer to
+defined names; `a'` or `a^{-1}` is the inverse of `a`. An element may also
+be written as cycles plus twist vector: `(1 6 8 7)(2 4 5) [0 2 0 2 1 2 0 2]`.";
+
+const COMMANDS: [&str; 13] = [
+    "matrix",
+    "net",
+    "order",
+    "type",
+    "list",
+    "del",
+    "undo",
+    "help",
+    "let",
+    "gen",
+    "print",
+    "assert_eq",
+    "diff",
+];
+
+#[derive(Debug, Clone)]
+struct Binding {
+    source: String,
+    w: WreathElm,
+}
+
+#[derive(Debug, Clone)]
+enum Change {
+    Name(String, Option<Binding>),
+    Generator(String, Option<Operation>),
+}
+
+/// State of an interactive session or script: the generators, the named
+/// elements and the history of changes to them.
+#[derive(Debug, Clone)]
+pub struct Session {
+    moves: MoveSet,
+    names: Vec<(String, Binding)>,
+    history: Vec<Change>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new(MoveSet::cube())
+    }
+}
+
+// `(1 2 3) [..]` or `e [..]`, as opposed to a word like `(R U)^3`
+fn is_literal(expr: &str) -> bool {
+    let expr = expr.trim_start();
+    if let Some(rest) = expr.strip_prefix('(') {
+        rest.trim_start().starts_with(|c: char| c.is_ascii_digit())
+    } else if let Some(rest) = expr.strip_prefix('e') {
+        rest.trim_start().starts_with('[')
+    } else {
+        false
+    }
+}
+
+// splits `a, b` at the first comma outside of brackets
+fn split_top_level(s: &str) -> Option<(&str, &str)> {
+    let mut depth = 0i32;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => return Some((&s[..i], &s[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+impl Session {
+    pub fn new(moves: MoveSet) -> Self {
+        Self {
+            moves,
+            names: vec![],
+            history: vec![],
+        }
+    }
+
+    pub fn moves(&self) -> &MoveSet {
+        &self.moves
+    }
+
+    fn set(&mut self, name: &str, binding: Option<Binding>) -> Option<Binding> {
+        let old = self
+            .names
+            .iter()
+            .position(|(n, _)| n == name)
+            .map(|i| self.names.remove(i).1);
+        if let Some(b) = binding {
+            self.names.push((name.to_string(), b));
+        }
+        old
+    }
+
+    // the generators together with the defined names, so that the usual
+    // notation parser resolves both
+    fn scope(&self) -> MoveSet {
+        let mut opes = self.moves.generators().to_vec();
+        for (name, b) in self.names.iter() {
+            opes.push(Operation::new(name, b.w.clone()));
+        }
+        MoveSet::new(opes)
+    }
+
+    pub fn eval(&self, expr: &str) -> Result<WreathElm> {
+        Ok(decode(&self.eval_matrix(expr)?))
+    }
+
+    fn eval_matrix(&self, expr: &str) -> Result<Matrix> {
+        if is_literal(expr) {
+            let w = expr.parse::<WreathElm>()?;
+            if w.size() != self.moves.size() {
+                bail!("expected {} twists, got {}", self.moves.size(), w.size());
+            }
+            return Ok(w.get_matrix());
+        }
+
+        let word = self.scope().parse_word(expr)?;
+        Ok(evaluate(&word, &identity_matrix(self.moves.size())))
+    }
+
+    fn is_valid_name(&self, name: &str) -> bool {
+        let mut chars = name.chars();
+        matches!(chars.next(), Some(c) if c.is_ascii_lowercase())
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && self.moves.get(name).is_none()
+            && !COMMANDS.contains(&name)
+    }
+
+    /// Binds `name` to the value of `expr`.
+    pub fn define(&mut self, name: &str, expr: &str) -> Result<WreathElm> {
+        if !self.is_valid_name(name) {
+            bail!("`{}` is not a valid name", name);
+        }
+        let w = self.eval(expr)?;
+        let old = self.set(
+            name,
+            Some(Binding {
+                source: expr.trim().to_string(),
+                w: w.clone(),
+            }),
+        );
+        self.history.push(Change::Name(name.to_string(), old));
+        Ok(w)
+    }
+
+    /// Adds a generator, or replaces the one with the same name.
+    pub fn define_generator(&mut self, name: &str, w: WreathElm) -> Result<()> {
+        let mut chars = name.chars();
+        if !matches!(chars.next(), Some(c) if c.is_ascii_uppercase())
+            || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            bail!(
+                "generator names start with an upper case letter: `{}`",
+                name
+            );
+        }
+        if !self.moves.generators().is_empty() && w.size() != self.moves.size() {
+            bail!("expected {} twists, got {}", self.moves.size(), w.size());
+        }
+
+        let old = self.moves.get(name).cloned();
+        self.moves.insert(Operation::new(name, w));
+        self.history.push(Change::Generator(name.to_string(), old));
+        Ok(())
+    }
+
+    /// Renders `expr` as `matrix`, `net`, `order`, `type` or, for an empty
+    /// `what`, as cycles and twist vector.
+    pub fn show(&self, what: &str, expr: &str) -> Result<String> {
+        match what {
+            "" => Ok(self.eval(expr)?.to_string()),
+            "matrix" => Ok(format_matrix(&self.eval_matrix(expr)?)),
+            "net" => Ok(FaceletCube::from_wreath(&self.eval(expr)?)?.net()),
+            "order" => Ok(self.eval(expr)?.order().to_string()),
+            "type" => {
+                let w = self.eval(expr)?;
+                Ok(format!("{:?}", w.get_replacement().cycle_type(w.size())))
+            }
+            _ => bail!("cannot show as `{}`", what),
+        }
+    }
+
+    // value of one side of `assert_eq`: `order x`, `type x`, a number, a
+    // cycle type like `[3, 1, 1]` or an element, commutators included
+    fn value(&self, side: &str) -> Result<String> {
+        let side = side.trim();
+        let cycle_type = side
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .is_some_and(|s| {
+                s.chars()
+                    .all(|c| c.is_ascii_digit() || c == ',' || c == ' ')
+            });
+        if side.parse::<usize>().is_ok() || cycle_type {
+            return Ok(side.replace(' ', ""));
+        }
+        for what in ["order", "type"] {
+            if let Some(expr) = side.strip_prefix(what) {
+                if expr.starts_with(char::is_whitespace) {
+                    return Ok(self.show(what, expr)?.replace(' ', ""));
+                }
+            }
+        }
+        Ok(self.eval(side)?.to_string())
+    }
+
+    pub fn assert_eq(&self, args: &str) -> Result<()> {
+        let (a, b) = split_top_level(args).ok_or_else(|| anyhow!("assert_eq needs two sides"))?;
+        let (x, y) = (self.value(a)?, self.value(b)?);
+        if x != y {
+            bail!(
+                "assertion failed: `{}` is {}, `{}` is {}",
+                a.trim(),
+                x,
+                b.trim(),
+                y
+            );
+        }
+        Ok(())
+    }
+
+    /// Runs one statement of a script. Returns the text of `print`.
+    pub fn statement(&mut self, line: &str) -> Result<Option<String>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((c, a)) => (c, a.trim()),
+            None => (line, ""),
+        };
+
+        match command {
+            "let" => {
+                let (name, expr) = arg
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("expected `let <name> = <expr>`"))?;
+                self.define(name.trim(), expr)?;
+                Ok(None)
+            }
+            "gen" => {
+                let (name, elem) = arg
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("expected `gen <Name> = <cycles> [<twists>]`"))?;
+                self.define_generator(name.trim(), elem.parse()?)?;
+                Ok(None)
+            }
+            "print" => {
+                if let Some(text) = arg.strip_prefix('"') {
+                    let text = text
+                        .strip_suffix('"')
+                        .ok_or_else(|| anyhow!("unterminated string"))?;
+                    return Ok(Some(text.to_string()));
+                }
+                let (what, expr) = match arg.split_once(char::is_whitespace) {
+                    Some((w, e)) if ["matrix", "net", "order", "type"].contains(&w) => (w, e),
+                    _ => ("", arg),
+                };
+                Ok(Some(self.show(what, expr)?))
+            }
+            "assert_eq" => {
+                self.assert_eq(arg)?;
+                Ok(None)
+            }
+            "diff" => {
+                let (a, b) = split_top_level(arg).ok_or_else(|| anyhow!("diff needs two sides"))?;
+                let (a, b) = (self.eval(a)?, self.eval(b)?);
+                let d = diff(&a, &b)?;
+                Ok(Some(format!("{}\n\n{}", d, format_positions(&a, &b, &d))))
+            }
+            _ => bail!("unknown statement `{}`", command),
+        }
+    }
+
+    fn undo(&mut self) -> Result<String> {
+        match self.history.pop() {
+            Some(Change::Name(name, old)) => {
+                let restored = old.as_ref().map(|b| format!("{} = {}", name, b.w));
+                self.set(&name, old);
+                Ok(restored.unwrap_or(format!("removed {}", name)))
+            }
+            Some(Change::Generator(name, Some(old))) => {
+                let res = format!("{} = {}", name, old.w);
+                self.moves.insert(old);
+                Ok(res)
+            }
+            Some(Change::Generator(name, None)) => {
+                self.moves = MoveSet::new(
+                    self.moves
+                        .generators()
+                        .iter()
+                        .filter(|o| o.name != name)
+                        .cloned()
+                        .collect(),
+                );
+                Ok(format!("removed {}", name))
+            }
+            None => bail!("nothing to undo"),
+        }
+    }
+
+    /// Runs one line interactively and returns what should be printed.
+    pub fn execute(&mut self, line: &str) -> Result<String> {
+        let line = line.trim();
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((c, a)) => (c, a.trim()),
+            None => (line, ""),
+        };
+
+        match command {
+            "" => Ok(String::new()),
+            _ if command.starts_with('#') => Ok(String::new()),
+            "let" | "print" | "assert_eq" | "diff" => Ok(self.statement(line)?.unwrap_or_default()),
+            "gen" => {
+                self.statement(line)?;
+                let name = arg.split('=').next().unwrap_or("").trim();
+                Ok(format!("{} = {}", name, self.moves.get(name).unwrap().w))
+            }
+            "help" => Ok(HELP.to_string()),
+            "list" => {
+                let mut res = String::new();
+                for (name, b) in self.names.iter() {
+                    writeln!(res, "{} = {}    # {}", name, b.source, b.w).unwrap();
+                }
+                Ok(res.trim_end().to_string())
+            }
+            "del" => {
+                let old = self
+                    .set(arg, None)
+                    .ok_or_else(|| anyhow!("`{}` is not defined", arg))?;
+                self.history.push(Change::Name(arg.to_string(), Some(old)));
+                Ok(format!("removed {}", arg))
+            }
+            "undo" => self.undo(),
+            "matrix" | "net" | "order" | "type" => self.show(command, arg),
+            _ => match line.split_once('=') {
+                Some((name, expr)) => {
+                    let name = name.trim();
+                    let w = self.define(name, expr)?;
+                    Ok(format!("{} = {}", name, w))
+                }
+                None => self.show("", line),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assert_eq_evaluates_commutators() {
+        let mut session = Session::new(MoveSet::cube());
+        session.statement("let a = [R, U]").unwrap();
+        session.statement("assert_eq a, [R, U]").unwrap();
+        session.statement("assert_eq order [R, U], 6").unwrap();
+        assert!(session.statement("assert_eq [R, U], [F, D]").is_err());
+        assert!(session.statement("assert_eq a, [U, R]").is_err());
+    }
+
+    #[test]
+    fn assert_eq_compares_cycle_types() {
+        let session = Session::new(MoveSet::cube());
+        session.assert_eq("type R, [4, 1, 1, 1, 1]").unwrap();
+        session
+            .assert_eq("type [R, U], [2, 2, 1, 1, 1, 1]")
+            .unwrap();
+        assert!(session.assert_eq("type R, [3,1,1,1,1,1]").is_err());
+    }
+}
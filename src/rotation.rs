@@ -0,0 +1,165 @@
+use crate::cube::MoveSet;
+use crate::symmetry::rotations;
+use crate::wreath::{decode, evaluate, identity_matrix, rotate, Operation, WreathElm};
+use crate::{Mod3, Replacement};
+use anyhow::{bail, Result};
+
+// The face turns move all eight corners, so the state of the 2x2x2 also
+// records how the cube as a whole is held. Turning the cube back so that one
+// corner is solved removes that: the other seven corners then carry the
+// whole state, as if only U, R and F were ever turned. Every face turns
+// clockwise seen from that face, so U and D' together are the rotation y and
+// U is the same as D up to rotation, R as L and F as B.
+
+/// DBL, the corner U, R and F leave in place.
+pub const REFERENCE: usize = 6;
+
+const ROTATIONS: [(&str, &str); 3] = [("x", "R L'"), ("y", "U D'"), ("z", "F B'")];
+
+/// The whole-cube rotations x, y and z, which turn like R, U and F.
+pub fn rotation_moves() -> Vec<Operation> {
+    let moves = MoveSet::cube();
+    ROTATIONS
+        .iter()
+        .map(|(name, word)| {
+            let m = evaluate(
+                &moves.parse_word(word).unwrap(),
+                &identity_matrix(moves.size()),
+            );
+            Operation::new(name, decode(&m))
+        })
+        .collect()
+}
+
+/// The 2x2x2 move set together with x, y and z.
+pub fn moves_with_rotations() -> MoveSet {
+    let mut moves = MoveSet::cube();
+    for o in rotation_moves() {
+        moves.insert(o);
+    }
+    moves
+}
+
+/// The state turned as a whole so that corner `reference` is solved,
+/// together with the rotation applied after `state` to get there.
+pub fn normalize(state: &WreathElm, reference: usize) -> Result<(WreathElm, Operation)> {
+    if state.size() != 8 || reference == 0 || reference > 8 {
+        bail!("normalizing needs a state of the 2x2x2 and one of its corners");
+    }
+    let res = rotations()
+        .into_iter()
+        .map(|r| {
+            let w = decode(&rotate(&state.get_matrix(), &r.rotation.get_matrix()));
+            (w, Operation::new(&r.name, r.rotation))
+        })
+        .find(|(w, _)| {
+            w.get_replacement().replace(reference) == reference
+                && w.get_vector()[reference - 1] == Mod3::Zero
+        })
+        .unwrap();
+    Ok(res)
+}
+
+/// Whether two states differ only by how the whole cube is held.
+pub fn same_up_to_rotation(a: &WreathElm, b: &WreathElm) -> Result<bool> {
+    Ok(normalize(a, REFERENCE)?.0 == normalize(b, REFERENCE)?.0)
+}
+
+/// The state of the other seven corners, renumbered in order, when corner
+/// `reference` is solved.
+pub fn to_seven(state: &WreathElm, reference: usize) -> Result<WreathElm> {
+    if state.size() != 8 || reference == 0 || reference > 8 {
+        bail!("expected a state of 8 corners and a corner of 1..=8");
+    }
+    if state.get_replacement().replace(reference) != reference
+        || state.get_vector()[reference - 1] != Mod3::Zero
+    {
+        bail!("corner {} is not solved in {}", reference, state);
+    }
+    let squeeze = |p: usize| if p > reference { p - 1 } else { p };
+    let corr_book = (1..=8)
+        .filter(|&p| p != reference)
+        .map(|p| squeeze(state.get_replacement().replace(p)))
+        .collect::<Vec<_>>();
+    let vector = (1..=8)
+        .filter(|&q| q != reference)
+        .map(|q| state.get_vector()[q - 1])
+        .collect();
+    WreathElm::try_new(Replacement::from_images(&corr_book)?, vector)
+}
+
+/// The 8-corner state of a 7-corner state, with corner `reference` solved.
+pub fn from_seven(state: &WreathElm, reference: usize) -> Result<WreathElm> {
+    if state.size() != 7 || reference == 0 || reference > 8 {
+        bail!("expected a state of 7 corners and a corner of 1..=8");
+    }
+    let widen = |p: usize| if p >= reference { p + 1 } else { p };
+    let mut corr_book = vec![reference; 8];
+    let mut vector = vec![Mod3::Zero; 8];
+    for p in 1..=7 {
+        corr_book[widen(p) - 1] = widen(state.get_replacement().replace(p));
+        vector[widen(p) - 1] = state.get_vector()[p - 1];
+    }
+    WreathElm::try_new(Replacement::from_images(&corr_book)?, vector)
+}
+
+/// The 7-corner model of any state: normalized, then without DBL.
+pub fn seven_corner_state(state: &WreathElm) -> Result<WreathElm> {
+    to_seven(&normalize(state, REFERENCE)?.0, REFERENCE)
+}
+
+/// U, R and F acting on the seven corners other than DBL.
+pub fn seven_corner_moves() -> MoveSet {
+    let cube = MoveSet::cube();
+    MoveSet::new(
+        ["U", "R", "F"]
+            .iter()
+            .map(|&name| {
+                let w = to_seven(&cube.get(name).unwrap().w, REFERENCE).unwrap();
+                Operation::new(name, w)
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    #[test]
+    fn rotations_have_order_4() {
+        for o in rotation_moves() {
+            assert_eq!(o.w.order(), 4, "{}", o.name);
+        }
+        assert_eq!(moves_with_rotations().generators().len(), 9);
+    }
+
+    #[test]
+    fn seven_corners_round_trip() {
+        let moves = MoveSet::cube();
+        let gens = moves.generators();
+        let mut rng = XorShift::new(13);
+        for _ in 0..20 {
+            let word = (0..20)
+                .map(|_| gens[rng.below(gens.len())].clone())
+                .collect::<Vec<_>>();
+            let state = decode(&evaluate(&word, &identity_matrix(moves.size())));
+            for reference in 1..=8 {
+                let (normal, r) = normalize(&state, reference).unwrap();
+                assert_eq!(decode(&rotate(&state.get_matrix(), &r.m)), normal);
+                assert!(same_up_to_rotation(&state, &normal).unwrap());
+                let seven = to_seven(&normal, reference).unwrap();
+                assert_eq!(seven.size(), 7);
+                assert_eq!(from_seven(&seven, reference).unwrap(), normal);
+            }
+        }
+    }
+
+    #[test]
+    fn unsolved_reference_is_rejected() {
+        let state = "(5 6) [0 0 0 0 0 0 0 0]".parse::<WreathElm>().unwrap();
+        assert!(to_seven(&state, REFERENCE).is_err());
+        assert!(to_seven(&state, 1).is_ok());
+    }
+}
@@ -0,0 +1,47 @@
+use crate::cube::MoveSet;
+use crate::repl::Session;
+use anyhow::{anyhow, Result};
+use std::io::Write;
+
+/// Runs a worksheet script line by line on the puzzle with the given moves,
+/// writing everything it prints to `out`. Stops at the first failing
+/// statement and reports its line number. See `repl::HELP` for the statements.
+pub fn run_script(moves: MoveSet, source: &str, out: &mut impl Write) -> Result<()> {
+    let mut session = Session::new(moves);
+    for (i, line) in source.lines().enumerate() {
+        let printed = session
+            .statement(line)
+            .map_err(|e| anyhow!("line {}: {}", i + 1, e))?;
+        if let Some(text) = printed {
+            writeln!(out, "{}", text)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_worksheet_script() {
+        let source = include_str!("../scripts/worksheet.cube");
+        let mut out = vec![];
+        run_script(MoveSet::cube(), source, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "=== generators ===");
+        assert!(out.contains("{Replacement: (1 4 5 8), Vector: [1, 0, 0, 2, 1, 0, 0, 2]}"));
+        assert!(out.contains("{Replacement: e, Vector: [1, 1, 0, 0, 1, 1, 2, 0]}"));
+        assert_eq!(lines.last(), Some(&"solved"));
+    }
+
+    #[test]
+    fn failures_name_the_line() {
+        let source = "let a = R\n\nassert_eq order a, 3\nprint \"unreached\"\n";
+        let mut out = vec![];
+        let err = run_script(MoveSet::cube(), source, &mut out).unwrap_err();
+        assert!(err.to_string().starts_with("line 3:"), "{}", err);
+        assert!(out.is_empty());
+    }
+}
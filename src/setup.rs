@@ -0,0 +1,328 @@
+use crate::conjugacy::twisted_cycle_type;
+use crate::cube::MoveSet;
+use crate::solver::{alter_book, Algorithm};
+use crate::wreath::{Operation, WreathElm};
+use crate::{Mod3, Replacement};
+use std::collections::HashSet;
+
+// Elements are kept as images and twists while searching, multiplied like
+// `rotate`: in `a * b` the element b acts first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Elm {
+    perm: Vec<usize>,
+    twist: Vec<Mod3>,
+}
+
+impl Elm {
+    fn from_wreath(w: &WreathElm) -> Self {
+        Self {
+            perm: w.get_replacement().images(w.size()),
+            twist: w.get_vector().to_vec(),
+        }
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let n = self.perm.len();
+        let mut twist = self.twist.clone();
+        for p in 1..=n {
+            // other carries p to q, then self carries q on
+            let q = other.perm[p - 1];
+            twist[self.perm[q - 1] - 1] += other.twist[q - 1];
+        }
+        Self {
+            perm: other.perm.iter().map(|&q| self.perm[q - 1]).collect(),
+            twist,
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        let n = self.perm.len();
+        let mut perm = vec![0; n];
+        let mut twist = vec![Mod3::Zero; n];
+        for p in 1..=n {
+            let q = self.perm[p - 1];
+            perm[q - 1] = p;
+            twist[p - 1] = self.twist[q - 1].add_inv();
+        }
+        Self { perm, twist }
+    }
+}
+
+/// A known algorithm carried onto other pieces: `setup A undo`, where
+/// `undo` is the inverse of `setup`.
+#[derive(Debug, Clone)]
+pub struct Setup {
+    pub setup: Vec<Operation>,
+    pub undo: Vec<Operation>,
+    pub algorithm: Algorithm,
+}
+
+impl Setup {
+    pub fn word(&self) -> Vec<Operation> {
+        let mut res = self.setup.clone();
+        res.extend(self.algorithm.word.iter().cloned());
+        res.extend(self.undo.iter().cloned());
+        res
+    }
+
+    /// The label as a conjugate, e.g. `[RU^{-1}: [RF]U[RF]U^{-1}]`, or the
+    /// label of the algorithm if no setup is needed.
+    pub fn label(&self) -> String {
+        if self.setup.is_empty() {
+            return self.algorithm.label.clone();
+        }
+        let setup = self
+            .setup
+            .iter()
+            .map(|o| o.name.as_str())
+            .collect::<String>();
+        format!("[{}: {}]", setup, self.algorithm.label)
+    }
+
+    pub fn to_algorithm(&self, name: &str) -> Algorithm {
+        Algorithm::new(name, &self.label(), self.word())
+    }
+}
+
+/// The effect a setup should achieve.
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// Exactly this state, twists included.
+    State(WreathElm),
+    /// This arrangement of the pieces, with any twists, as `alter_book`
+    /// leaves the twists to a later phase.
+    Permutation(Replacement),
+}
+
+impl Target {
+    fn matches(&self, elm: &Elm, goal: &Elm) -> bool {
+        match self {
+            Target::State(_) => elm == goal,
+            Target::Permutation(_) => elm.perm == goal.perm,
+        }
+    }
+
+    // whether some conjugate of `w` can match
+    fn conjugate_to(&self, w: &WreathElm) -> bool {
+        match self {
+            Target::State(t) => {
+                t.size() == w.size() && twisted_cycle_type(t) == twisted_cycle_type(w)
+            }
+            Target::Permutation(r) => {
+                r.get_correct_k().is_none_or(|k| k <= w.size())
+                    && r.cycle_type(w.size()) == w.get_replacement().cycle_type(w.size())
+            }
+        }
+    }
+
+    fn goal(&self, n: usize) -> Elm {
+        match self {
+            Target::State(t) => Elm::from_wreath(t),
+            Target::Permutation(r) => Elm {
+                perm: r.images(n),
+                twist: vec![Mod3::Zero; n],
+            },
+        }
+    }
+}
+
+/// For every target, the shortest setup S with at most `max_depth` moves and
+/// an algorithm A of `library` such that S A S^{-1} is the target. Setups are
+/// tried in breadth-first order and the algorithms in library order.
+pub fn find_setups(
+    moves: &MoveSet,
+    library: &[Algorithm],
+    targets: &[Target],
+    max_depth: usize,
+) -> Vec<Option<Setup>> {
+    let n = moves.size();
+    let mut res = vec![None; targets.len()];
+
+    // only targets conjugate to some algorithm can be reached
+    let effects = library
+        .iter()
+        .map(|a| (a.effect(), a))
+        .filter(|(w, _)| w.size() == n)
+        .collect::<Vec<_>>();
+    let mut open = targets
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| effects.iter().any(|(w, _)| t.conjugate_to(w)))
+        .map(|(i, t)| (i, t, t.goal(n)))
+        .collect::<Vec<_>>();
+    let effects = effects
+        .iter()
+        .map(|(w, a)| (Elm::from_wreath(w), *a))
+        .collect::<Vec<_>>();
+
+    let mut opes = moves.generators().to_vec();
+    opes.extend(moves.generators().iter().map(|o| moves.inverse_of(o)));
+    let steps = opes
+        .iter()
+        .map(|o| Elm::from_wreath(&o.w))
+        .collect::<Vec<_>>();
+
+    let e = Elm::from_wreath(&WreathElm::e(n));
+    let mut seen = HashSet::from([e.clone()]);
+    let mut layer = vec![(e, vec![])];
+    for depth in 0..=max_depth {
+        let mut next_layer = vec![];
+        for (s, word) in layer {
+            let s_inv = s.inverse();
+            open.retain(|(i, t, goal)| {
+                let found = effects
+                    .iter()
+                    .find(|(a, _)| t.matches(&s.mul(a).mul(&s_inv), goal));
+                if let Some((_, a)) = found {
+                    res[*i] = Some(setup_of(moves, &word, a));
+                }
+                found.is_none()
+            });
+            if depth == max_depth || open.is_empty() {
+                continue;
+            }
+            for (o, step) in opes.iter().zip(steps.iter()) {
+                let next = s.mul(step);
+                if seen.insert(next.clone()) {
+                    let mut word = word.clone();
+                    word.push(o.clone());
+                    next_layer.push((next, word));
+                }
+            }
+        }
+        layer = next_layer;
+    }
+    res
+}
+
+fn setup_of(moves: &MoveSet, word: &[Operation], a: &Algorithm) -> Setup {
+    Setup {
+        setup: word.to_vec(),
+        undo: word.iter().rev().map(|o| moves.inverse_of(o)).collect(),
+        algorithm: a.clone(),
+    }
+}
+
+/// The shortest setup turning an algorithm of `library` into `target`, see
+/// `find_setups`.
+pub fn find_setup(
+    moves: &MoveSet,
+    library: &[Algorithm],
+    target: &Target,
+    max_depth: usize,
+) -> Option<Setup> {
+    find_setups(moves, library, std::slice::from_ref(target), max_depth)
+        .pop()
+        .flatten()
+}
+
+/// `library` followed by the inverse of each of its algorithms, named by
+/// their permutation and labelled `(A)^{-1}`.
+pub fn with_inverses(moves: &MoveSet, library: &[Algorithm]) -> Vec<Algorithm> {
+    let mut res = library.to_vec();
+    res.extend(library.iter().map(|a| {
+        let word = a
+            .word
+            .iter()
+            .rev()
+            .map(|o| moves.inverse_of(o))
+            .collect::<Vec<_>>();
+        let inverse = Algorithm::new("", &format!("({})^{{-1}}", a.label), word);
+        Algorithm {
+            name: inverse.effect().get_replacement().to_string(),
+            ..inverse
+        }
+    }));
+    res
+}
+
+/// A 3-cycle for every three corners, `alter_book` and its
+/// inverses carried by setups of at most `max_depth` moves. Cycles out of
+/// reach are left out.
+pub fn complete_alter_book(moves: &MoveSet, max_depth: usize) -> Vec<Algorithm> {
+    let n = moves.size();
+    let library = with_inverses(moves, &alter_book(moves));
+    let mut cycles = vec![];
+    for a in 1..=n {
+        for b in a + 1..=n {
+            for c in a + 1..=n {
+                if c != b {
+                    cycles.push(Replacement::new(vec![vec![a, b, c]]));
+                }
+            }
+        }
+    }
+    let targets = cycles
+        .iter()
+        .map(|r| Target::Permutation(r.clone()))
+        .collect::<Vec<_>>();
+
+    cycles
+        .iter()
+        .zip(find_setups(moves, &library, &targets, max_depth))
+        .filter_map(|(r, s)| s.map(|s| s.to_algorithm(&r.to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wreath::{decode, evaluate, identity_matrix};
+
+    fn state_of(moves: &MoveSet, word: &[Operation]) -> WreathElm {
+        decode(&evaluate(word, &identity_matrix(moves.size())))
+    }
+
+    #[test]
+    fn setups_reach_permutations() {
+        let moves = MoveSet::cube();
+        let library = with_inverses(&moves, &alter_book(&moves));
+        let targets = ["(1 2 3)", "(1 5 7)", "(2 8 4)", "(3 6 8)"]
+            .iter()
+            .map(|s| Target::Permutation(s.parse().unwrap()))
+            .collect::<Vec<_>>();
+        for (target, found) in targets
+            .iter()
+            .zip(find_setups(&moves, &library, &targets, 4))
+        {
+            let Target::Permutation(r) = target else {
+                unreachable!()
+            };
+            let found = found.unwrap_or_else(|| panic!("no setup for {}", r));
+            let state = state_of(&moves, &found.word());
+            assert_eq!(state.get_replacement().images(8), r.images(8), "{}", r);
+        }
+    }
+
+    #[test]
+    fn setups_reach_states() {
+        let moves = MoveSet::cube();
+        let library = with_inverses(&moves, &alter_book(&moves));
+        for setup in ["R U'", "F", "U2 R"] {
+            let mut word = moves.parse_word(setup).unwrap();
+            let undo = word
+                .iter()
+                .rev()
+                .map(|o| moves.inverse_of(o))
+                .collect::<Vec<_>>();
+            word.extend(library[1].word.iter().cloned());
+            word.extend(undo);
+            let target = state_of(&moves, &word);
+
+            let found = find_setup(&moves, &library, &Target::State(target.clone()), 3).unwrap();
+            assert_eq!(state_of(&moves, &found.word()), target, "{}", setup);
+            assert!(found.setup.len() <= moves.parse_word(setup).unwrap().len());
+        }
+    }
+
+    #[test]
+    fn unreachable_targets() {
+        let moves = MoveSet::cube();
+        let library = with_inverses(&moves, &alter_book(&moves));
+        // the library holds 3-cycles only
+        let swap = Target::Permutation("(1 2)".parse().unwrap());
+        assert!(find_setup(&moves, &library, &swap, 3).is_none());
+        let twist = Target::State("e [1 2 0 0 0 0 0 0]".parse().unwrap());
+        assert!(find_setup(&moves, &library, &twist, 3).is_none());
+    }
+}
@@ -0,0 +1,344 @@
+use crate::cube::MoveSet;
+use crate::notation::reduce_word;
+use crate::solve_combination;
+use crate::wreath::{decode, evaluate, identity_matrix, Operation, WreathElm};
+use anyhow::{bail, Result};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Debug, Clone)]
+pub struct Algorithm {
+    pub name: String,
+    pub label: String,
+    pub word: Vec<Operation>,
+}
+
+impl Algorithm {
+    pub fn new(name: &str, label: &str, word: Vec<Operation>) -> Self {
+        Self {
+            name: name.to_string(),
+            label: label.to_string(),
+            word,
+        }
+    }
+
+    pub fn effect(&self) -> WreathElm {
+        let n = self.word.first().map(|o| o.w.size()).unwrap_or(0);
+        decode(&evaluate(&self.word, &identity_matrix(n)))
+    }
+}
+
+/// 3-cycles of neighbouring corners built from commutators.
+pub fn alter_book(moves: &MoveSet) -> Vec<Algorithm> {
+    [
+        ("(1 2 3)", "[RF]U[RF]U^{-1}", "R' F' R F U R' F' R F U'"),
+        ("(2 3 4)", "[LF]U[LF]U^{-1}", "L' F' L F U L' F' L F U'"),
+        ("(3 4 5)", "[UR]B^{-1}[UR]B", "U' R' U R B' U' R' U R B"),
+        ("(4 5 6)", "[DR]B^{-1}[DR]B", "D' R' D R B' D' R' D R B"),
+        ("(5 6 7)", "[RB]D[RB]D^{-1}", "R' B' R B D R' B' R B D'"),
+        ("(6 7 8)", "[LB]D[LB]D^{-1}", "L' B' L B D L' B' L B D'"),
+    ]
+    .into_iter()
+    .map(|(name, label, word)| Algorithm::new(name, label, moves.parse_word(word).unwrap()))
+    .collect()
+}
+
+/// Pure twists whose vectors span the orientations with total twist zero.
+pub fn twist_book(moves: &MoveSet) -> Vec<Algorithm> {
+    [
+        (
+            "x_1",
+            "(BU^{-1})^3(B^{-1}U^{-1})^5",
+            "B U' B U' B U' B' U' B' U' B' U' B' U' B' U'",
+        ),
+        (
+            "x_2",
+            "(U^{-1}F)^3(U^{-1}F^{-1})^5",
+            "U' F U' F U' F U' F' U' F' U' F' U' F' U' F'",
+        ),
+        (
+            "x_3",
+            "(B^{-1}D)^3(B^{-1}D^{-1})^5",
+            "B' D B' D B' D B' D' B' D' B' D' B' D' B' D'",
+        ),
+        (
+            "x_4",
+            "(B^{-1}L)^3(B^{-1}L^{-1})^5",
+            "B' L B' L B' L B' L' B' L' B' L' B' L' B' L'",
+        ),
+        (
+            "x_5",
+            "(D^{-1}R)^3(D^{-1}R^{-1})^5",
+            "D' R D' R D' R D' R' D' R' D' R' D' R' D' R'",
+        ),
+        (
+            "x_6",
+            "(D^{-1}B)^3(D^{-1}B^{-1})^5",
+            "D' B D' B D' B D' B' D' B' D' B' D' B' D' B'",
+        ),
+        (
+            "y",
+            "(FD)^5(F^{-1}D)^3(D^{-1}R)^3(D^{-1}R^{-1})^5",
+            "F D F D F D F D F D F' D F' D F' D D' R D' R D' R D' R' D' R' D' R' D' R' D' R'",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, label, word)| Algorithm::new(name, label, moves.parse_word(word).unwrap()))
+    .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Parity,
+    Permutation,
+    Twist,
+}
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    pub phase: Phase,
+    pub name: String,
+    pub label: String,
+    pub word: Vec<Operation>,
+}
+
+/// Steps are kept in written order: the solved state is
+/// `steps[0] * steps[1] * ... * state`.
+#[derive(Debug, Clone)]
+pub struct Solution {
+    pub steps: Vec<Step>,
+}
+
+impl Solution {
+    /// The moves of all steps in written order, with what cancels between
+    /// and within the steps removed by `reduce_word`.
+    pub fn operations(&self) -> Vec<Operation> {
+        let word = self
+            .steps
+            .iter()
+            .flat_map(|s| s.word.iter().cloned())
+            .collect::<Vec<_>>();
+        reduce_word(&word)
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        self.steps.iter().map(|s| s.label.clone()).collect()
+    }
+}
+
+// Shortest sequence of the given permutations (as image arrays) which,
+// multiplied from the left one after another, turns `start` into the identity.
+fn search_cycles(start: Vec<usize>, gens: &[Vec<usize>]) -> Option<Vec<usize>> {
+    let n = start.len();
+    let goal = (1..=n).collect::<Vec<_>>();
+    let mut parent: HashMap<Vec<usize>, Option<(Vec<usize>, usize)>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    parent.insert(start.clone(), None);
+    queue.push_back(start);
+
+    while let Some(now) = queue.pop_front() {
+        if now == goal {
+            let mut path = vec![];
+            let mut cur = now;
+            while let Some(Some((prev, g))) = parent.get(&cur).cloned() {
+                path.push(g);
+                cur = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for (gi, g) in gens.iter().enumerate() {
+            // left multiplication: i -> g(now(i))
+            let next = now.iter().map(|&x| g[x - 1]).collect::<Vec<_>>();
+            if !parent.contains_key(&next) {
+                parent.insert(next.clone(), Some((now.clone(), gi)));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Solves any legal state of the 2x2x2 in two phases: first the corners are
+/// put in place with the 3-cycles of `alter_book` (after a single `U` if the
+/// permutation is odd), then the remaining twist is removed with a combination
+/// of `twist_book` found by linear algebra over Z/3.
+pub fn algebraic_solve(state: &WreathElm) -> Result<Solution> {
+    let moves = MoveSet::cube();
+    let n = moves.size();
+    if state.size() != n {
+        bail!("state has {} pieces, expected {}", state.size(), n);
+    }
+
+    let mut steps = vec![];
+    let mut now = state.get_matrix();
+
+    // parity
+    if state.get_replacement().is_odd() {
+        let odd = moves
+            .generators()
+            .iter()
+            .find(|o| o.w.get_replacement().is_odd())
+            .unwrap();
+        now = evaluate(std::slice::from_ref(odd), &now);
+        steps.push(Step {
+            phase: Phase::Parity,
+            name: odd.w.get_replacement().to_string(),
+            label: odd.name.clone(),
+            word: vec![odd.clone()],
+        });
+    }
+
+    // permutation
+    let alters = alter_book(&moves);
+    let mut cycle_gens = vec![];
+    let mut cycle_opts = vec![];
+    for alt in alters.iter() {
+        let effect = alt.effect();
+        // a 3-cycle applied twice is its inverse
+        for times in 1..=2 {
+            let mut g = (1..=n).collect::<Vec<_>>();
+            for _ in 0..times {
+                g = g
+                    .iter()
+                    .map(|&x| effect.get_replacement().replace(x))
+                    .collect();
+            }
+            cycle_gens.push(g);
+            cycle_opts.push((alt, times));
+        }
+    }
+
+    let start = decode(&now).get_replacement().images(n);
+    let path = match search_cycles(start, &cycle_gens) {
+        Some(path) => path,
+        None => bail!("corners cannot be placed with the known 3-cycles"),
+    };
+
+    let mut perm_steps = vec![];
+    for gi in path {
+        let (alt, times) = cycle_opts[gi];
+        for _ in 0..times {
+            now = evaluate(&alt.word, &now);
+            perm_steps.push(Step {
+                phase: Phase::Permutation,
+                name: alt.name.clone(),
+                label: alt.label.clone(),
+                word: alt.word.clone(),
+            });
+        }
+    }
+    perm_steps.reverse();
+
+    // twist
+    let twists = twist_book(&moves);
+    let basis = twists
+        .iter()
+        .map(|t| t.effect().get_vector().to_vec())
+        .collect::<Vec<_>>();
+    let target = decode(&now)
+        .get_vector()
+        .iter()
+        .map(|m| m.add_inv())
+        .collect::<Vec<_>>();
+    let coeffs = match solve_combination(&basis, &target) {
+        Some(coeffs) => coeffs,
+        None => bail!("illegal state: twist cannot be solved"),
+    };
+
+    let mut twist_steps = vec![];
+    for (t, &c) in twists.iter().zip(coeffs.iter()) {
+        let c: usize = c.into();
+        for _ in 0..c {
+            twist_steps.push(Step {
+                phase: Phase::Twist,
+                name: t.name.clone(),
+                label: t.label.clone(),
+                word: t.word.clone(),
+            });
+        }
+    }
+
+    steps.reverse();
+    let mut all = twist_steps;
+    all.extend(perm_steps);
+    all.extend(steps);
+    let solution = Solution { steps: all };
+
+    let res = decode(&evaluate(&solution.operations(), &state.get_matrix()));
+    if !res.is_e() {
+        bail!("solution does not solve the state: {}", res);
+    }
+
+    Ok(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::{state_at, STATES};
+    use crate::notation::format_word;
+    use crate::rng::XorShift;
+    use crate::{Mod3, Replacement};
+
+    fn solves(state: &WreathElm) -> Vec<Operation> {
+        let word = algebraic_solve(state).unwrap().operations();
+        assert!(
+            decode(&evaluate(&word, &state.get_matrix())).is_e(),
+            "{}",
+            state
+        );
+        word
+    }
+
+    #[test]
+    fn problem_3() {
+        let state = "(1 6 8 7)(2 4 5) [0 2 0 2 1 2 0 2]"
+            .parse::<WreathElm>()
+            .unwrap();
+        solves(&state);
+    }
+
+    #[test]
+    fn random_legal_states() {
+        let moves = MoveSet::cube();
+        let gens = moves.generators();
+        let mut rng = XorShift::new(7);
+        for _ in 0..10 {
+            let word = (0..25)
+                .map(|_| gens[rng.below(gens.len())].clone())
+                .collect::<Vec<_>>();
+            solves(&decode(&evaluate(&word, &identity_matrix(moves.size()))));
+            solves(&state_at(rng.below(STATES)).unwrap());
+        }
+    }
+
+    #[test]
+    fn solutions_are_reduced() {
+        let moves = MoveSet::cube();
+        let word = moves.parse_word("R U F").unwrap();
+        let solution = solves(&decode(&evaluate(&word, &identity_matrix(8))));
+        for pair in solution.windows(2) {
+            assert!(pair[0].w.inverse() != pair[1].w, "{}", format_word(pair));
+        }
+        for run in solution.windows(3) {
+            assert!(run[0].w != run[1].w || run[1].w != run[2].w);
+        }
+    }
+
+    #[test]
+    fn illegal_states_are_rejected() {
+        let mut twist = vec![Mod3::Zero; 8];
+        twist[0] = Mod3::One;
+        let twisted = WreathElm::new(Replacement::e(), twist.clone());
+        assert!(algebraic_solve(&twisted).is_err());
+
+        // a single swap is a legal state of the 2x2x2, 8! arrangements are
+        // reachable, but not together with a single twisted corner
+        let swap = Replacement::new(vec![vec![1, 2]]);
+        solves(&WreathElm::new(swap.clone(), vec![Mod3::Zero; 8]));
+        assert!(algebraic_solve(&WreathElm::new(swap, twist)).is_err());
+
+        assert!(algebraic_solve(&WreathElm::e(7)).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::conjugacy::{conjugators, CornerGroup};
+use crate::cube::MoveSet;
+use crate::linalg::{Mod3Matrix, Mod3Vector};
+use crate::rotation::rotation_moves;
+use crate::wreath::{decode, rotate, Operation, WreathElm};
+use crate::{Mod3, Replacement};
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+
+// On the 2x2x2 a whole-cube rotation is a pair of opposite face turns, so
+// the rotations are states themselves and act on other states by
+// conjugation, s X s^{-1}. The other 24 symmetries follow the mirror in the
+// plane between L and R, which swaps the corners pairwise and, turning
+// counter-clockwise into clockwise, negates every twist.
+
+// UFR-UFL, UBL-UBR, DBR-DBL and DFL-DFR
+const MIRROR: [usize; 8] = [2, 1, 4, 3, 6, 5, 8, 7];
+
+/// A symmetry of the 2x2x2: a whole-cube rotation, preceded by the left-right
+/// mirror if `mirror` is set.
+#[derive(Debug, Clone)]
+pub struct Symmetry {
+    pub name: String,
+    pub rotation: WreathElm,
+    pub mirror: bool,
+}
+
+fn mirrored(w: &WreathElm) -> WreathElm {
+    let images = w.get_replacement().images(MIRROR.len());
+    let mut corr_book = vec![0; MIRROR.len()];
+    let mut vector = vec![Mod3::Zero; MIRROR.len()];
+    for p in 1..=MIRROR.len() {
+        corr_book[MIRROR[p - 1] - 1] = MIRROR[images[p - 1] - 1];
+        vector[MIRROR[p - 1] - 1] = w.get_vector()[p - 1].add_inv();
+    }
+    WreathElm::new(Replacement::from_correspond_book(&corr_book), vector)
+}
+
+impl Symmetry {
+    /// The image of a state of the 2x2x2.
+    pub fn apply(&self, state: &WreathElm) -> WreathElm {
+        let state = if self.mirror {
+            mirrored(state)
+        } else {
+            state.clone()
+        };
+        let m = rotate(
+            &rotate(&self.rotation.rev(), &state.get_matrix()),
+            &self.rotation.get_matrix(),
+        );
+        decode(&m)
+    }
+
+    /// The image of a move sequence, move by move, so that the result reaches
+    /// `apply` of the state of `word`. Fails if some image is not a move of
+    /// `moves` or the inverse of one.
+    pub fn apply_word(&self, moves: &MoveSet, word: &[Operation]) -> Result<Vec<Operation>> {
+        let mut opes = moves.generators().to_vec();
+        opes.extend(moves.generators().iter().map(|o| moves.inverse_of(o)));
+        word.iter()
+            .map(|o| {
+                let image = self.apply(&o.w);
+                opes.iter()
+                    .find(|p| p.w == image)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("{} takes `{}` to no move", self.name, o.name))
+            })
+            .collect()
+    }
+
+    // the permutation of the positions
+    fn positions(&self) -> Replacement {
+        let n = MIRROR.len();
+        let images = self.rotation.get_replacement().images(n);
+        let images = match self.mirror {
+            true => MIRROR.iter().map(|&q| images[q - 1]).collect::<Vec<_>>(),
+            false => images,
+        };
+        Replacement::from_images(&images).unwrap()
+    }
+
+    /// Number of legal states of the 2x2x2 left unchanged.
+    pub fn fixed_states(&self) -> u128 {
+        let n = MIRROR.len();
+        if !self.mirror && self.rotation.is_e() {
+            return CornerGroup::cube().order();
+        }
+        let rho = self.positions();
+        // a fixed state has a permutation commuting with the positions; its
+        // twists then solve `apply(v) = v`, an affine system over Z/3
+        let mut res = 0;
+        for sigma in conjugators(&rho, &rho, n) {
+            let base = self.apply(&WreathElm::new(sigma.clone(), vec![Mod3::Zero; n]));
+            let b = Mod3Vector::from(base.get_vector());
+            let columns = (0..n)
+                .map(|i| {
+                    let unit = Mod3Vector::unit(n, i);
+                    let w = self.apply(&WreathElm::new(sigma.clone(), unit.clone().into_vec()));
+                    let column = &(&Mod3Vector::from(w.get_vector()) - &b) - &unit;
+                    // the last row asks for total twist zero
+                    Mod3Vector::new([column.into_vec(), vec![Mod3::One]].concat())
+                })
+                .collect::<Vec<_>>();
+            let a = Mod3Matrix::from_columns(&columns);
+            let target = Mod3Vector::new([(-&b).into_vec(), vec![Mod3::Zero]].concat());
+            if a.solve(&target).is_some() {
+                res += 3u128.pow(a.kernel().len() as u32);
+            }
+        }
+        res
+    }
+}
+
+// `x x` is written `x2`
+fn rotation_name(word: &[String]) -> String {
+    let mut res: Vec<String> = vec![];
+    for (i, r) in word.iter().enumerate() {
+        if i > 0 && word[i - 1] == *r && res.last().is_some_and(|l| l == r) {
+            let l = res.last_mut().unwrap();
+            *l = format!("{}2", r.trim_end_matches('\''));
+        } else {
+            res.push(r.clone());
+        }
+    }
+    res.join(" ")
+}
+
+/// The 24 whole-cube rotations, named by a shortest word in x, y and z.
+pub fn rotations() -> Vec<Symmetry> {
+    let mut gens = vec![];
+    for o in rotation_moves() {
+        gens.push((o.name.clone(), o.w.clone()));
+        gens.push((format!("{}'", o.name), o.w.inverse()));
+    }
+
+    let e = WreathElm::e(MIRROR.len());
+    let mut res = vec![Symmetry {
+        name: "e".to_string(),
+        rotation: e.clone(),
+        mirror: false,
+    }];
+    let mut queue = VecDeque::from([(vec![], e)]);
+    while let Some((word, w)) = queue.pop_front() {
+        for (name, g) in gens.iter() {
+            let next = decode(&rotate(&w.get_matrix(), &g.get_matrix()));
+            if res.iter().all(|s| s.rotation != next) {
+                let mut word = word.clone();
+                word.push(name.clone());
+                res.push(Symmetry {
+                    name: rotation_name(&word),
+                    rotation: next.clone(),
+                    mirror: false,
+                });
+                queue.push_back((word, next));
+            }
+        }
+    }
+    res
+}
+
+/// The 48 symmetries: the rotations, then the rotations after the mirror,
+/// whose names end in `m`.
+pub fn symmetries() -> Vec<Symmetry> {
+    let rotations = rotations();
+    let mirrored = rotations
+        .iter()
+        .map(|s| Symmetry {
+            name: match s.name.as_str() {
+                "e" => "m".to_string(),
+                name => format!("{} m", name),
+            },
+            rotation: s.rotation.clone(),
+            mirror: true,
+        })
+        .collect::<Vec<_>>();
+    [rotations, mirrored].concat()
+}
+
+// images then twists, compared lexicographically
+fn key(w: &WreathElm) -> (Vec<usize>, Vec<usize>) {
+    (
+        w.get_replacement().images(w.size()),
+        w.get_vector().iter().map(|&m| usize::from(m)).collect(),
+    )
+}
+
+/// The least image of `state` under `syms` with the symmetry giving it.
+pub fn canonical_under(state: &WreathElm, syms: &[Symmetry]) -> (WreathElm, Symmetry) {
+    syms.iter()
+        .map(|s| (s.apply(state), s))
+        .min_by_key(|(w, _)| key(w))
+        .map(|(w, s)| (w, s.clone()))
+        .unwrap()
+}
+
+/// The representative of `state` among its images under all 48 symmetries.
+pub fn canonical_under_symmetry(state: &WreathElm) -> WreathElm {
+    canonical_under(state, &symmetries()).0
+}
+
+/// A symmetry of `syms` taking `a` to `b`, if any.
+pub fn relating_symmetry(a: &WreathElm, b: &WreathElm, syms: &[Symmetry]) -> Option<Symmetry> {
+    syms.iter().find(|s| s.apply(a) == *b).cloned()
+}
+
+/// Number of legal states of the 2x2x2 up to `syms`, which must form a
+/// group, by Burnside's lemma.
+pub fn symmetry_classes(syms: &[Symmetry]) -> u128 {
+    let fixed = syms.iter().map(|s| s.fixed_states()).sum::<u128>();
+    assert_eq!(fixed % syms.len() as u128, 0, "the symmetries are no group");
+    fixed / syms.len() as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coord::{state_at, STATES};
+    use crate::rng::XorShift;
+    use crate::wreath::{evaluate, identity_matrix};
+
+    fn product(a: &WreathElm, b: &WreathElm) -> WreathElm {
+        decode(&rotate(&b.get_matrix(), &a.get_matrix()))
+    }
+
+    #[test]
+    fn symmetries_are_homomorphisms() {
+        let syms = symmetries();
+        assert_eq!(syms.len(), 48);
+        let mut rng = XorShift::new(3);
+        for _ in 0..10 {
+            let a = state_at(rng.below(STATES)).unwrap();
+            let b = state_at(rng.below(STATES)).unwrap();
+            for s in syms.iter() {
+                assert_eq!(
+                    s.apply(&product(&a, &b)),
+                    product(&s.apply(&a), &s.apply(&b)),
+                    "{}",
+                    s.name
+                );
+            }
+        }
+        // closed under composition
+        let w = "(1 2 3)(4 5) [1 0 2 0 0 0 0 0]"
+            .parse::<WreathElm>()
+            .unwrap();
+        let images = syms.iter().map(|s| key(&s.apply(&w))).collect::<Vec<_>>();
+        for s in syms.iter() {
+            for t in syms.iter() {
+                assert!(images.contains(&key(&s.apply(&t.apply(&w)))));
+            }
+        }
+    }
+
+    #[test]
+    fn words_go_to_words() {
+        let moves = MoveSet::cube();
+        let word = moves.parse_word("R U' F D2 L B'").unwrap();
+        let state = decode(&evaluate(&word, &identity_matrix(moves.size())));
+        for s in symmetries() {
+            let image = s.apply_word(&moves, &word).unwrap();
+            assert_eq!(image.len(), word.len());
+            assert_eq!(
+                decode(&evaluate(&image, &identity_matrix(moves.size()))),
+                s.apply(&state),
+                "{}",
+                s.name
+            );
+        }
+    }
+
+    #[test]
+    fn class_counts() {
+        assert_eq!(symmetry_classes(&rotations()), 3678234);
+        assert_eq!(symmetry_classes(&symmetries()), 1841970);
+    }
+}
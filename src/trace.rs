@@ -0,0 +1,147 @@
+use crate::facelet::FaceletCube;
+use crate::json::{encode_wreath, ReplacementForm};
+use crate::wreath::{decode, rotate, Operation, WreathElm};
+use anyhow::Result;
+use serde_json::json;
+
+/// The state after one move of a traced sequence. Step 0 is the initial
+/// state, without a move.
+#[derive(Debug, Clone)]
+pub struct TraceStep {
+    pub step: usize,
+    pub name: Option<String>,
+    pub state: WreathElm,
+    /// Positions (1-based) whose piece or twist changed with this move.
+    pub changed: Vec<usize>,
+}
+
+// the piece at each position and its twist
+fn contents(w: &WreathElm) -> Vec<(usize, usize)> {
+    (1..=w.size())
+        .map(|q| (w.piece_at(q), w.get_vector()[q - 1].into()))
+        .collect()
+}
+
+/// Evaluates `word` on `init` one move at a time, in the order of
+/// `evaluate`, i.e. starting from the last operation of the word.
+pub fn trace(word: &[Operation], init: &WreathElm) -> Vec<TraceStep> {
+    let mut res = vec![TraceStep {
+        step: 0,
+        name: None,
+        state: init.clone(),
+        changed: vec![],
+    }];
+
+    let mut now = init.get_matrix();
+    for (i, o) in word.iter().rev().enumerate() {
+        now = rotate(&now, &o.m);
+        let state = decode(&now);
+        let before = contents(&res[i].state);
+        let changed = contents(&state)
+            .iter()
+            .zip(before.iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(q, _)| q + 1)
+            .collect();
+        res.push(TraceStep {
+            step: i + 1,
+            name: Some(o.name.clone()),
+            state,
+            changed,
+        });
+    }
+    res
+}
+
+/// One line per step with the move, the changed positions and the state.
+pub fn format_table(steps: &[TraceStep]) -> String {
+    let mut lines = vec![format!(
+        "{:<5} {:<8} {:<16} state",
+        "step", "move", "changed"
+    )];
+    for s in steps.iter() {
+        let changed = s
+            .changed
+            .iter()
+            .map(|q| q.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        lines.push(format!(
+            "{:<5} {:<8} {:<16} {}",
+            s.step,
+            s.name.as_deref().unwrap_or("-"),
+            changed,
+            s.state
+        ));
+    }
+    lines.join("\n")
+}
+
+/// One JSON object per line, with the state as in `json::encode_wreath`.
+pub fn format_json_lines(steps: &[TraceStep]) -> String {
+    steps
+        .iter()
+        .map(|s| {
+            json!({
+                "step": s.step,
+                "move": s.name,
+                "state": encode_wreath(&s.state, ReplacementForm::Cycles),
+                "changed": s.changed,
+            })
+            .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The net after each step, with the stickers of changed corners in lower
+/// case.
+pub fn format_nets(steps: &[TraceStep]) -> Result<String> {
+    let mut blocks = vec![];
+    for s in steps.iter() {
+        let cube = FaceletCube::from_wreath(&s.state)?;
+        blocks.push(format!(
+            "{} {}\n{}",
+            s.step,
+            s.name.as_deref().unwrap_or("start"),
+            cube.net_marked(&s.changed)
+        ));
+    }
+    Ok(blocks.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::MoveSet;
+    use crate::wreath::evaluate;
+
+    #[test]
+    fn one_step_per_move() {
+        let moves = MoveSet::cube();
+        let word = moves.parse_word("R U' F2 D").unwrap();
+        let init = "(1 2) [1 2 0 0 0 0 0 0]".parse::<WreathElm>().unwrap();
+        let steps = trace(&word, &init);
+
+        assert_eq!(steps.len(), word.len() + 1);
+        assert_eq!(steps[0].state, init);
+        assert!(steps[0].name.is_none());
+        // the last operation of the word acts first
+        for (s, o) in steps[1..].iter().zip(word.iter().rev()) {
+            assert_eq!(s.name.as_deref(), Some(o.name.as_str()));
+            assert_eq!(s.changed.len(), 4, "{}", o.name);
+        }
+        assert_eq!(
+            steps.last().unwrap().state,
+            decode(&evaluate(&word, &init.get_matrix()))
+        );
+    }
+
+    #[test]
+    fn empty_word() {
+        let steps = trace(&[], &WreathElm::e(8));
+        assert_eq!(steps.len(), 1);
+        assert!(steps[0].changed.is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+use rubikcube::latex::Report;
+use rubikcube::solver::{alter_book, twist_book};
+use rubikcube::wreath::identity_matrix;
+use rubikcube::{
+    algebraic_solve, decode, evaluate, print_matrix, rotate, solve_combination, Matrix, MoveSet,
+    Replacement, WreathElm, WSIZE,
+};
+use std::collections::HashMap;
+
+fn show(report: &mut Report, name: &str, m: &Matrix) {
+    print_matrix(name, m);
+    println!("{}", decode(m));
+    report.add(name, m);
+}
+
+fn show_labeled(report: &mut Report, name: &str, m: &Matrix, labels: &[String]) {
+    print_matrix(name, m);
+    println!("{}", decode(m));
+    println!("About operation: {:?}\n", labels);
+    report.add_with_labels(name, m, labels);
+}
+
+/// The worked examples of the original homework worksheet. Everything shown
+/// is also collected into a report which can be typeset.
+pub fn run() -> Report {
+    let mut report = Report::new("Worked examples");
+    let moves = MoveSet::cube();
+    let u = moves.get("U").unwrap().clone();
+    let d = moves.get("D").unwrap().clone();
+    let l = moves.get("L").unwrap().clone();
+    let r = moves.get("R").unwrap().clone();
+    let f = moves.get("F").unwrap().clone();
+    let b = moves.get("B").unwrap().clone();
+    let u_rev = u.rev();
+    let d_rev = d.rev();
+    let r_rev = r.rev();
+    let f_rev = f.rev();
+    let b_rev = b.rev();
+
+    let wreath = [
+        u.clone(),
+        d.clone(),
+        l.clone(),
+        r.clone(),
+        f.clone(),
+        b.clone(),
+    ];
+
+    for ope in wreath.iter() {
+        print_matrix(&ope.name, &ope.m);
+        report.add(&ope.name, &ope.m);
+    }
+
+    let ur = rotate(&r.m, &u.m);
+    show(&mut report, "UR", &ur);
+
+    let fr = rotate(&r.m, &f.m);
+    show(&mut report, "FR", &fr);
+
+    let bu = rotate(&u.m, &b.m);
+    show(&mut report, "BU", &bu);
+
+    let binv_u = rotate(&u.m, &b_rev.m);
+    show(&mut report, "B^{-1}U", &binv_u);
+
+    let test_case = vec![&f.m, &d.m, &f.m, &d.m, &f.m, &d.m, &f.m, &d.m, &f.m];
+    let mut res = d.m.clone();
+    for m in test_case.into_iter().rev() {
+        res = rotate(&res, m);
+    }
+    show(&mut report, "test", &res);
+
+    // (1)
+
+    let problem_1 = vec![
+        &d.m, &d.m, &b.m, &b.m, &d_rev.m, &f.m, &f.m, &d.m, &b.m, &b.m, &d_rev.m, &f.m,
+        &f.m, // &dinv,
+    ];
+
+    let mut res = d_rev.m.clone();
+    for m in problem_1.into_iter().rev() {
+        res = rotate(&res, m);
+    }
+    show(&mut report, "(1) (i)", &res);
+
+    let problem_2 = vec![
+        &r_rev.m, &f_rev.m, &u.m, &f.m, &r.m, &d_rev.m, &r.m, &r.m, &f_rev.m, &r.m, &u_rev.m,
+        &r_rev.m, &f.m, &r.m, &r.m, // &d.m,
+    ];
+
+    let mut res = d.m.clone();
+    for m in problem_2.into_iter().rev() {
+        res = rotate(&res, m);
+    }
+    show(&mut report, "(1) (ii)", &res);
+
+    // (2)
+    let problem_3 = WreathElm::new(
+        Replacement::new(vec![vec![1, 6, 8, 7], vec![2, 4, 5]]),
+        vec![0, 2, 0, 2, 1, 2, 0, 2]
+            .into_iter()
+            .map(|n| n.into())
+            .collect(),
+    );
+
+    let alter_book = alter_book(&moves)
+        .into_iter()
+        .map(|alt| (alt.name, (alt.label, alt.word)))
+        .collect::<HashMap<_, _>>();
+
+    let replace_part_alters = vec![
+        "(1 2 3)", "(2 3 4)", "(2 3 4)", "(3 4 5)", "(2 3 4)", "(2 3 4)", "(4 5 6)", "(4 5 6)",
+        "(5 6 7)", "(4 5 6)", "(3 4 5)", "(2 3 4)", "(2 3 4)", "(6 7 8)",
+    ];
+    let mut replace_part_opes = vec![];
+    let mut replace_part_vec = vec![];
+
+    for &alt in replace_part_alters.iter() {
+        let alter = alter_book.get(alt).unwrap();
+        replace_part_opes.push(alter.0.clone());
+        replace_part_vec.extend_from_slice(alter.1.as_slice());
+    }
+
+    replace_part_vec.push(u.clone());
+    let mut res = problem_3.get_matrix();
+    for o in replace_part_vec.into_iter().rev() {
+        res = rotate(&res, &o.m);
+    }
+    show_labeled(&mut report, "(2) replacement", &res, &replace_part_opes);
+
+    let problem_4 = res;
+
+    let twist_book = twist_book(&moves)
+        .into_iter()
+        .map(|t| (t.label, t.word))
+        .collect::<Vec<_>>();
+
+    let twist_vecs = twist_book
+        .iter()
+        .map(|(_, vec)| {
+            decode(&evaluate(vec, &identity_matrix(WSIZE)))
+                .get_vector()
+                .to_vec()
+        })
+        .collect::<Vec<_>>();
+
+    let target = decode(&problem_4)
+        .get_vector()
+        .iter()
+        .map(|m| m.add_inv())
+        .collect::<Vec<_>>();
+
+    let coeffs = solve_combination(&twist_vecs, &target).expect("twist is not reachable");
+
+    let mut v_inv = vec![];
+    let mut opes = vec![];
+
+    for ((label, vec), &c) in twist_book.iter().zip(coeffs.iter()) {
+        let c: usize = c.into();
+        for _ in 0..c {
+            v_inv.extend_from_slice(vec);
+            opes.push(label.clone());
+        }
+    }
+
+    let mut res = problem_4;
+    for o in v_inv.into_iter().rev() {
+        res = rotate(&res, &o.m);
+        // print_matrix("dump", &res);
+    }
+    show_labeled(&mut report, "(2) twist", &res, &opes);
+
+    let solution = algebraic_solve(&problem_3).unwrap();
+    let res = evaluate(&solution.operations(), &problem_3.get_matrix());
+    show_labeled(&mut report, "(2) algebraic_solve", &res, &solution.labels());
+    report
+}
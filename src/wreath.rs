@@ -0,0 +1,356 @@
+use crate::{Mod3, Replacement};
+use anyhow::{anyhow, bail, Result};
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub struct WreathElm {
+    replacement: Replacement,
+    vector: Vec<Mod3>,
+}
+
+pub const WSIZE: usize = 8;
+pub type Matrix = Vec<Vec<Option<Mod3>>>;
+
+impl WreathElm {
+    pub fn new(replacement: Replacement, vector: Vec<Mod3>) -> Self {
+        Self {
+            replacement,
+            vector,
+        }
+    }
+
+    /// Like `new`, but fails if the permutation moves a point beyond the
+    /// end of the vector.
+    pub fn try_new(replacement: Replacement, vector: Vec<Mod3>) -> Result<Self> {
+        if replacement.get_k() > vector.len() {
+            bail!(
+                "permutation moves point {} but the vector has only {} entries",
+                replacement.get_k(),
+                vector.len()
+            );
+        }
+        Ok(Self::new(replacement, vector))
+    }
+
+    pub fn e(n: usize) -> Self {
+        Self::new(Replacement::e(), vec![Mod3::Zero; n])
+    }
+
+    pub fn get_replacement(&self) -> &Replacement {
+        &self.replacement
+    }
+
+    pub fn get_vector(&self) -> &[Mod3] {
+        &self.vector
+    }
+
+    pub fn size(&self) -> usize {
+        self.vector.len()
+    }
+
+    /// The piece sitting at position `q`.
+    pub fn piece_at(&self, q: usize) -> usize {
+        self.replacement.rev_find(q).unwrap_or(q)
+    }
+
+    pub fn is_e(&self) -> bool {
+        self.replacement == Replacement::e() && self.vector.iter().all(|&m| m == Mod3::Zero)
+    }
+
+    pub fn get_matrix(&self) -> Matrix {
+        let n = self.size();
+        let mut res = vec![vec![None; n]; n];
+        for (i, line) in res.iter_mut().enumerate() {
+            let j = self.replacement.rev_find(i + 1).unwrap_or(i + 1);
+            line[j - 1] = Some(self.vector[i]);
+        }
+
+        res
+    }
+
+    /// The group inverse: the permutation is inverted and every twist
+    /// is undone at the position the piece came from.
+    pub fn inverse(&self) -> Self {
+        let corr_book = (1..=self.size())
+            .map(|i| self.replacement.rev_find(i).unwrap_or(i))
+            .collect::<Vec<_>>();
+        let vector = (1..=self.size())
+            .map(|j| self.vector[self.replacement.replace(j) - 1].add_inv())
+            .collect();
+        Self::new(Replacement::from_correspond_book(&corr_book), vector)
+    }
+
+    /// For every cycle of the permutation, fixed points included, the twist
+    /// a piece picks up going once around it and the resulting period: the
+    /// cycle length, times 3 unless that twist is zero.
+    pub fn periods(&self) -> Vec<(Vec<usize>, Mod3, usize)> {
+        let mut cycles = self.replacement.cycles();
+        for i in 1..=self.size() {
+            if self.replacement.replace(i) == i {
+                cycles.push(vec![i]);
+            }
+        }
+        cycles.sort_by_key(|c| c[0]);
+
+        cycles
+            .into_iter()
+            .map(|c| {
+                let twist = c
+                    .iter()
+                    .fold(Mod3::Zero, |acc, &i| acc + self.vector[i - 1]);
+                let period = if twist == Mod3::Zero {
+                    c.len()
+                } else {
+                    3 * c.len()
+                };
+                (c, twist, period)
+            })
+            .collect()
+    }
+
+    /// Smallest `k > 0` with `self^k` the identity, the least common
+    /// multiple of the periods.
+    pub fn order(&self) -> usize {
+        fn gcd(a: usize, b: usize) -> usize {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+        self.periods()
+            .iter()
+            .fold(1, |acc, &(_, _, p)| acc / gcd(acc, p) * p)
+    }
+
+    pub fn rev(&self) -> Matrix {
+        self.inverse().get_matrix()
+    }
+}
+
+impl PartialEq for WreathElm {
+    fn eq(&self, other: &Self) -> bool {
+        self.replacement == other.replacement && self.vector == other.vector
+    }
+}
+
+impl Eq for WreathElm {}
+
+impl Display for WreathElm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{Replacement: {}, Vector: [{}]}}",
+            self.replacement,
+            self.vector
+                .iter()
+                .map(|&m| {
+                    let n: usize = m.into();
+                    n.to_string()
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Parses a state written as cycles plus twist vector, e.g.
+/// `(1 6 8 7)(2 4 5) [0 2 0 2 1 2 0 2]`. The vector fixes the size.
+impl FromStr for WreathElm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let open = s
+            .find('[')
+            .ok_or_else(|| anyhow!("missing twist vector `[...]` in `{}`", s))?;
+        let close = s
+            .rfind(']')
+            .filter(|&c| c > open)
+            .ok_or_else(|| anyhow!("missing `]` in `{}`", s))?;
+        if !s[close + 1..].trim().is_empty() {
+            bail!(
+                "unexpected `{}` after the twist vector",
+                s[close + 1..].trim()
+            );
+        }
+
+        let vector = s[open + 1..close]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .map(|t| {
+                t.parse::<usize>()
+                    .map(Mod3::from)
+                    .map_err(|_| anyhow!("`{}` is not a twist", t))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let replacement = Replacement::parse_within(&s[..open], vector.len())?;
+
+        Self::try_new(replacement, vector)
+    }
+}
+
+pub fn identity_matrix(n: usize) -> Matrix {
+    WreathElm::e(n).get_matrix()
+}
+
+/// Returns the matrix product `other * now`.
+pub fn rotate(now: &Matrix, other: &Matrix) -> Matrix {
+    let size = now.len();
+    let mut res = vec![vec![None; size]; size];
+
+    for i in 0..size {
+        for j in 0..size {
+            if let Some(m) = other[i][j] {
+                let target_line = &now[j];
+                for k in 0..size {
+                    if let Some(n) = target_line[k] {
+                        res[i][k] = Some(n + m);
+                        break;
+                    }
+                }
+                break;
+            }
+        }
+    }
+
+    res
+}
+
+/// Multiplies the operations onto `init` in written order,
+/// i.e. returns `opes[0] * opes[1] * ... * init`.
+pub fn evaluate(opes: &[Operation], init: &Matrix) -> Matrix {
+    let mut res = init.clone();
+    for o in opes.iter().rev() {
+        res = rotate(&res, &o.m);
+    }
+    res
+}
+
+pub fn format_matrix(m: &Matrix) -> String {
+    m.iter()
+        .map(|line| {
+            line.iter()
+                .map(|n| {
+                    if let Some(n) = n {
+                        n.to_string()
+                    } else {
+                        "_  ".to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn print_matrix(name: &str, m: &Matrix) {
+    println!("=== {} ===", name);
+    println!("{}", format_matrix(m));
+    println!("=========");
+}
+
+pub fn decode(m: &Matrix) -> WreathElm {
+    let mut corr_book = vec![0; m.len()];
+
+    for (i, corr) in corr_book.iter_mut().enumerate() {
+        if let Some(j) = m.iter().position(|line| line[i].is_some()) {
+            *corr = j + 1;
+        }
+    }
+
+    let mut vector = vec![];
+    for line in m.iter() {
+        if let Some(&val) = line.iter().flatten().next() {
+            vector.push(val);
+        }
+    }
+
+    let replacement = Replacement::from_correspond_book(&corr_book);
+    WreathElm::new(replacement, vector)
+}
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub name: String,
+    pub w: WreathElm,
+    pub m: Matrix,
+}
+
+impl Operation {
+    pub fn new(name: &str, w: WreathElm) -> Self {
+        let m = w.get_matrix();
+        Operation {
+            name: name.to_string(),
+            w,
+            m,
+        }
+    }
+
+    pub fn rev(&self) -> Self {
+        let m = self.w.rev();
+        let w = decode(&m);
+        Operation {
+            name: format!("{}^{{-1}}", self.name),
+            w,
+            m,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::MoveSet;
+
+    fn state(s: &str) -> WreathElm {
+        s.parse().unwrap()
+    }
+
+    // the least k > 0 with w^k the identity, by repeated multiplication
+    fn order_by_powers(w: &WreathElm) -> usize {
+        let m = w.get_matrix();
+        let mut now = m.clone();
+        let mut k = 1;
+        while !decode(&now).is_e() {
+            now = rotate(&now, &m);
+            k += 1;
+        }
+        k
+    }
+
+    #[test]
+    fn order_of_known_states() {
+        assert_eq!(WreathElm::e(8).order(), 1);
+        assert_eq!(state("e [1 2 0 0 0 0 0 0]").order(), 3);
+        assert_eq!(state("(1 2) [0 0 0 0 0 0 0 0]").order(), 2);
+        assert_eq!(state("(1 2) [1 0 0 0 0 0 0 0]").order(), 6);
+        assert_eq!(state("(1 2 3)(4 5) [1 2 0 0 0 0 0 0]").order(), 6);
+        assert_eq!(state("(1 2 3)(4 5) [1 0 0 0 0 0 0 0]").order(), 18);
+    }
+
+    #[test]
+    fn order_agrees_with_powers() {
+        let moves = MoveSet::cube();
+        for alg in [
+            "R",
+            "R U",
+            "R U'",
+            "R U2 F'",
+            "R U R' U'",
+            "F R U' R' U' R U R' F'",
+        ] {
+            let word = moves.parse_word(alg).unwrap();
+            let w = decode(&evaluate(&word, &identity_matrix(moves.size())));
+            assert_eq!(w.order(), order_by_powers(&w), "{}", alg);
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_state() {
+        let w = state("(1 6 8 7)(2 4 5) [0 2 0 2 1 2 0 2]");
+        assert!(decode(&rotate(&w.get_matrix(), &w.rev())).is_e());
+        assert!(decode(&rotate(&w.rev(), &w.get_matrix())).is_e());
+    }
+}
@@ -0,0 +1,129 @@
+use serde_json::Value;
+use std::process::{Command, Output};
+
+fn rubikcube(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rubikcube"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(out: &Output) -> String {
+    String::from_utf8(out.stdout.clone()).unwrap()
+}
+
+fn json(out: &Output) -> Value {
+    serde_json::from_slice(&out.stdout).unwrap()
+}
+
+#[test]
+fn apply_prints_the_state() {
+    let out = rubikcube(&["apply", "R", "U"]);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(stdout(&out).contains("alg:   R U"));
+    assert!(stdout(&out).contains("state: {Replacement:"));
+}
+
+#[test]
+fn apply_as_json() {
+    let out = rubikcube(&["--format", "json", "apply", "U"]);
+    assert_eq!(out.status.code(), Some(0));
+    let v = json(&out);
+    assert_eq!(v["alg"], "U");
+    assert_eq!(
+        v["state"]["replacement"]["cycles"],
+        serde_json::json!([[1, 2, 3, 4]])
+    );
+    assert_eq!(
+        v["state"]["vector"],
+        serde_json::json!([0, 0, 0, 0, 0, 0, 0, 0])
+    );
+}
+
+#[test]
+fn trace_styles_and_json() {
+    let out = rubikcube(&["--format", "json", "apply", "--trace", "jsonl", "R U"]);
+    assert_eq!(out.status.code(), Some(0));
+    let lines = stdout(&out);
+    let steps = lines
+        .lines()
+        .map(|l| serde_json::from_str::<Value>(l).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(steps.len(), 3);
+    assert_eq!(steps[1]["move"], "U");
+
+    let out = rubikcube(&["--format", "json", "apply", "--trace", "table", "R U"]);
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn solve_as_json() {
+    let out = rubikcube(&["--format", "json", "solve", "R U F"]);
+    assert_eq!(out.status.code(), Some(0));
+    let v = json(&out);
+    assert_eq!(v["alg"], "R U F");
+    let length = v["length"].as_u64().unwrap() as usize;
+    assert_eq!(v["solution"].as_str().unwrap().split(' ').count(), length);
+    assert!(v["steps"].as_array().is_some_and(|s| !s.is_empty()));
+}
+
+#[test]
+fn usage_errors_exit_2() {
+    for args in [
+        &["frobnicate"][..],
+        &[],
+        &["apply"],
+        &["apply", "Q"],
+        &["--format", "yaml", "size"],
+        &["search", "--count", "0", "R"],
+    ] {
+        let out = rubikcube(args);
+        assert_eq!(out.status.code(), Some(2), "{:?}", args);
+        assert!(String::from_utf8_lossy(&out.stderr).contains("usage:"));
+    }
+}
+
+#[test]
+fn failures_exit_1() {
+    let out = rubikcube(&["run", "no/such/file.cube"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(!String::from_utf8_lossy(&out.stderr).contains("usage:"));
+}
+
+#[test]
+fn scramble_a_one_move_puzzle() {
+    let path = std::env::temp_dir().join(format!("rubikcube-{}-one.puzzle", std::process::id()));
+    std::fs::write(
+        &path,
+        "name one\npieces 2\nmodulus 3\nmove t = (1 2) [1 2]\n",
+    )
+    .unwrap();
+    let out = rubikcube(&[
+        "--puzzle",
+        path.to_str().unwrap(),
+        "scramble",
+        "--length",
+        "5",
+        "--seed",
+        "1",
+    ]);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert!(stdout(&out).starts_with("scramble: t"));
+}
+
+#[test]
+fn search_beyond_max_length() {
+    let out = rubikcube(&[
+        "--format",
+        "json",
+        "search",
+        "--max-length",
+        "3",
+        "R U F R' U2",
+    ]);
+    assert_eq!(out.status.code(), Some(0));
+    let v = json(&out);
+    assert_eq!(v["outcome"], "exhausted");
+    assert_eq!(v["complete_length"], 3);
+}